@@ -248,6 +248,9 @@ export const commands: { [key: string]: Command } = {
       run: {
         hide: false,
       },
+      update: {
+        hide: false,
+      },
     },
   },
   trust: {
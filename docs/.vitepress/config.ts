@@ -54,7 +54,9 @@ export default defineConfig({
             items: [
               { text: "asdf", link: "/dev-tools/backends/asdf" },
               { text: "cargo", link: "/dev-tools/backends/cargo" },
+              { text: "conda", link: "/dev-tools/backends/conda" },
               { text: "go", link: "/dev-tools/backends/go" },
+              { text: "http", link: "/dev-tools/backends/http" },
               { text: "npm", link: "/dev-tools/backends/npm" },
               { text: "pipx", link: "/dev-tools/backends/pipx" },
               { text: "spm", link: "/dev-tools/backends/spm" },
@@ -0,0 +1,1130 @@
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Debug, Display, Formatter};
+use std::path::{Path, PathBuf};
+
+use eyre::{bail, eyre, WrapErr};
+use indexmap::IndexMap;
+use indoc::formatdoc;
+use itertools::Itertools;
+use once_cell::sync::{Lazy, OnceCell};
+use regex::bytes::{Captures, NoExpand, Regex};
+use serde::de::DeserializeOwned;
+use serde_derive::{Deserialize, Serialize};
+use xx::regex;
+
+use crate::backend::{config_tool_options, Backend, BackendType};
+use crate::cache::{CacheManager, CacheManagerBuilder};
+use crate::cli::args::BackendArg;
+use crate::cmd::CmdLineRunner;
+use crate::config::SETTINGS;
+use crate::hash::ensure_checksum_sha256;
+use crate::http::HTTP;
+use crate::install_context::InstallContext;
+use crate::lockfile::LockfilePlatform;
+use crate::toolset::{ToolRequest, ToolVersion, ToolVersionOptions};
+use crate::{dirs, env, file, lockfile};
+
+/// installs packages and their dependencies from conda-forge without conda, e.g.:
+///
+/// ```toml
+/// [tools]
+/// "conda:ffmpeg" = "7"
+/// ```
+#[derive(Debug)]
+pub struct CondaBackend {
+    ba: BackendArg,
+}
+
+impl Backend for CondaBackend {
+    fn get_type(&self) -> BackendType {
+        BackendType::Conda
+    }
+
+    fn fa(&self) -> &BackendArg {
+        &self.ba
+    }
+
+    fn _list_remote_versions(&self) -> eyre::Result<Vec<String>> {
+        let channel = channel(&config_tool_options(&self.ba));
+        let versions = fetch_repodata(&channel, self.name())?
+            .remove(self.name())
+            .unwrap_or_default()
+            .into_iter()
+            .map(|p| p.version)
+            .unique()
+            .sorted_by(|a, b| compare_versions(a, b))
+            .collect();
+        Ok(versions)
+    }
+
+    fn install_version_impl(&self, ctx: &InstallContext) -> eyre::Result<()> {
+        SETTINGS.ensure_experimental("conda backend")?;
+        let channel = channel(&ctx.tv.request.options());
+        ctx.pr.set_message(format!("resolving {}", self.name()));
+        let repodata = fetch_repodata(&channel, self.name())?;
+        let packages = match lockfile::get_locked_platform(&ctx.tv)? {
+            Some(locked) if locked.url.is_some() => {
+                locked_packages(&repodata, self.name(), &locked)?
+            }
+            _ => resolve(&repodata, self.name(), &ctx.tv.version, &VIRTUAL_PACKAGES)?,
+        };
+        // e.g.: "3.12" for noarch python packages which are installed into lib/python3.12
+        let python_version = packages
+            .get("python")
+            .map(|p| p.version.split('.').take(2).join("."));
+
+        let install_path = ctx.tv.install_path();
+        file::remove_all(&install_path)?;
+        file::create_dir_all(&install_path)?;
+        // dependencies first so the files of the package itself win
+        for (name, pkg) in packages.iter().rev() {
+            let dependency = (name != self.name()).then_some(name.as_str());
+            self.install_package(
+                ctx,
+                pkg,
+                &install_path,
+                dependency,
+                python_version.as_deref(),
+            )?;
+        }
+        Ok(())
+    }
+
+    fn list_bin_paths(&self, tv: &ToolVersion) -> eyre::Result<Vec<PathBuf>> {
+        if let ToolRequest::System(..) = tv.request {
+            return Ok(vec![]);
+        }
+        let install_path = tv.install_path();
+        if cfg!(windows) {
+            Ok(vec![
+                install_path.join("Library").join("bin"),
+                install_path.join("Scripts"),
+                install_path,
+            ])
+        } else {
+            Ok(vec![install_path.join("bin")])
+        }
+    }
+}
+
+impl CondaBackend {
+    pub fn from_arg(ba: BackendArg) -> Self {
+        Self { ba }
+    }
+
+    /// extracts a package into the install path, which is shared by all of its dependencies
+    /// like a conda environment
+    fn install_package(
+        &self,
+        ctx: &InstallContext,
+        pkg: &CondaPackage,
+        install_path: &Path,
+        dependency: Option<&str>,
+        python_version: Option<&str>,
+    ) -> eyre::Result<()> {
+        let filename = pkg.filename();
+        let url = pkg.url.clone();
+        let tarball = ctx.tv.download_path().join(filename);
+        ctx.pr.set_message(format!("downloading {filename}"));
+        HTTP.download_file(&url, &tarball, Some(ctx.pr.as_ref()))?;
+        if let Some(sha256) = &pkg.sha256 {
+            ctx.pr.set_message(format!("checksum {filename}"));
+            ensure_checksum_sha256(&tarball, sha256, Some(ctx.pr.as_ref()))?;
+        }
+        match dependency {
+            Some(name) => {
+                lockfile::verify_dependency(&ctx.tv, name, &url, &tarball, Some(ctx.pr.as_ref()))?
+            }
+            None => {
+                lockfile::verify_artifact(&ctx.tv, Some(&url), &tarball, Some(ctx.pr.as_ref()))?
+            }
+        }
+
+        ctx.pr.set_message(format!("extracting {filename}"));
+        let tmp = ctx.tv.download_path().join("extract");
+        file::remove_all(&tmp)?;
+        file::create_dir_all(&tmp)?;
+        extract_package(&tarball, &tmp)?;
+        let mut paths = read_paths(&tmp)?;
+        let link = read_link(&tmp)?;
+        let noarch_python = link.noarch.as_ref().filter(|n| n.noarch_type == "python");
+        if noarch_python.is_some() {
+            let python_version = python_version.ok_or_else(|| {
+                eyre!("{filename} is a noarch python package but python is not a dependency")
+            })?;
+            let (site_packages, scripts) = noarch_python_dirs(python_version);
+            for p in &mut paths {
+                p.path = noarch_python_path(&p.path, &site_packages, scripts);
+            }
+            for (from, to) in [
+                ("site-packages", site_packages.as_str()),
+                ("python-scripts", scripts),
+            ] {
+                if tmp.join(from).exists() {
+                    file::create_dir_all(tmp.join(to).parent().unwrap())?;
+                    file::rename(tmp.join(from), tmp.join(to))?;
+                }
+            }
+        }
+        // package metadata, every package has one
+        file::remove_all(tmp.join("info"))?;
+        file::move_stripped(&tmp, install_path, 0)?;
+        file::remove_all(&tmp)?;
+        file::remove_all(&tarball)?;
+
+        for p in paths {
+            if let Some(placeholder) = &p.prefix_placeholder {
+                let binary = p.file_mode.as_deref() == Some("binary");
+                replace_prefix(
+                    &install_path.join(&p.path),
+                    placeholder,
+                    install_path,
+                    binary,
+                )?;
+            }
+        }
+        if let Some(noarch) = noarch_python {
+            for entry_point in &noarch.entry_points {
+                write_entry_point(install_path, entry_point)?;
+            }
+        }
+        self.run_post_link(ctx, pkg, install_path)
+    }
+
+    /// runs `bin/.<name>-post-link.sh` like conda does after a package has been linked
+    fn run_post_link(
+        &self,
+        ctx: &InstallContext,
+        pkg: &CondaPackage,
+        install_path: &Path,
+    ) -> eyre::Result<()> {
+        let (script, program) = if cfg!(windows) {
+            (format!("Scripts/.{}-post-link.bat", pkg.name), "cmd")
+        } else {
+            (format!("bin/.{}-post-link.sh", pkg.name), "sh")
+        };
+        let script = install_path.join(script);
+        if !script.exists() {
+            return Ok(());
+        }
+        ctx.pr.set_message(format!("post-link {}", pkg.name));
+        let mut cmd = CmdLineRunner::new(program);
+        if cfg!(windows) {
+            cmd = cmd.arg("/c");
+        }
+        cmd.arg(&script)
+            .current_dir(install_path)
+            .env("PREFIX", install_path)
+            .env("PKG_NAME", &pkg.name)
+            .env("PKG_VERSION", &pkg.version)
+            .env("PKG_BUILDNUM", pkg.build_number.to_string())
+            .with_pr(ctx.pr.as_ref())
+            .execute()
+            .wrap_err_with(|| format!("post-link script of {} failed", pkg.name))
+    }
+}
+
+/// a package in the `repodata.json` of a channel subdir
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct CondaPackage {
+    name: String,
+    version: String,
+    build: String,
+    #[serde(default)]
+    build_number: u64,
+    #[serde(default)]
+    depends: Vec<String>,
+    /// requirements of packages that aren't dependencies, they only apply if the package is
+    /// installed by something else
+    #[serde(default)]
+    constrains: Vec<String>,
+    sha256: Option<String>,
+    /// e.g.: `https://conda.anaconda.org/conda-forge/linux-64/ffmpeg-7.0.1-gpl_h4c12d27_104.conda`
+    #[serde(default)]
+    url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RepodataJson {
+    #[serde(default)]
+    packages: HashMap<String, CondaPackage>,
+    #[serde(default, rename = "packages.conda")]
+    packages_conda: HashMap<String, CondaPackage>,
+}
+
+/// packages of a channel for this platform and `noarch` by name
+type Repodata = HashMap<String, Vec<CondaPackage>>;
+
+/// the packages of a channel subdir. Packages are cached by name once they have been read from
+/// its `repodata.json` so later installs don't need to parse all of it again.
+struct RepodataIndex {
+    url: String,
+    dir: PathBuf,
+    repodata_path: PathBuf,
+    /// the names of all packages in the repodata
+    names: HashSet<String>,
+    repodata: OnceCell<Repodata>,
+}
+
+/// an entry of `info/paths.json` in a package
+#[derive(Debug, Deserialize)]
+struct CondaPath {
+    #[serde(rename = "_path")]
+    path: String,
+    prefix_placeholder: Option<String>,
+    file_mode: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CondaPaths {
+    paths: Vec<CondaPath>,
+}
+
+/// `info/link.json` in a package
+#[derive(Debug, Default, Deserialize)]
+struct CondaLink {
+    noarch: Option<CondaNoarch>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CondaNoarch {
+    #[serde(rename = "type")]
+    noarch_type: String,
+    /// e.g.: `black = black:patched_main`
+    #[serde(default)]
+    entry_points: Vec<String>,
+}
+
+impl CondaPackage {
+    fn filename(&self) -> &str {
+        self.url.rsplit('/').next().unwrap_or(&self.url)
+    }
+}
+
+/// a requirement of a package, e.g.: `python_abi 3.12.* *_cp312`
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Dependency {
+    name: String,
+    version: String,
+    build: String,
+}
+
+impl Dependency {
+    fn matches(&self, pkg: &CondaPackage) -> bool {
+        matches_spec(&pkg.version, &self.version) && matches_build(&pkg.build, &self.build)
+    }
+}
+
+impl Display for Dependency {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} {}", self.name, self.version, self.build)
+    }
+}
+
+fn channel(opts: &ToolVersionOptions) -> String {
+    opts.get("channel")
+        .cloned()
+        .unwrap_or_else(|| "conda-forge".to_string())
+}
+
+/// the conda name of the current platform
+fn subdir() -> &'static str {
+    match (env::consts::OS, env::consts::ARCH) {
+        ("linux", "x86_64") => "linux-64",
+        ("linux", "aarch64") => "linux-aarch64",
+        ("macos", "x86_64") => "osx-64",
+        ("macos", "aarch64") => "osx-arm64",
+        ("windows", "x86_64") => "win-64",
+        _ => "unsupported",
+    }
+}
+
+/// virtual packages of the system that dependencies like `__glibc >=2.17` are checked against,
+/// other virtual packages like `__unix` are assumed to be provided
+static VIRTUAL_PACKAGES: Lazy<Vec<CondaPackage>> = Lazy::new(|| {
+    let version = |name: &str, cmd: duct::Expression| {
+        let out = cmd.stderr_null().read().ok()?;
+        let version = out.split_whitespace().last()?;
+        Some(virtual_package(name, version))
+    };
+    match env::consts::OS {
+        // e.g.: "glibc 2.36", fails on musl
+        "linux" => version("__glibc", cmd!("getconf", "GNU_LIBC_VERSION")),
+        "macos" => version("__osx", cmd!("sw_vers", "-productVersion")),
+        _ => None,
+    }
+    .into_iter()
+    .collect()
+});
+
+fn virtual_package(name: &str, version: &str) -> CondaPackage {
+    CondaPackage {
+        name: name.to_string(),
+        version: version.to_string(),
+        build: "0".to_string(),
+        build_number: 0,
+        depends: vec![],
+        constrains: vec![],
+        sha256: None,
+        url: String::new(),
+    }
+}
+
+/// `name` and every package it may depend on from the channel
+fn fetch_repodata(channel: &str, name: &str) -> eyre::Result<Repodata> {
+    let indexes = [subdir(), "noarch"]
+        .into_iter()
+        .map(|subdir| RepodataIndex::fetch(channel, subdir))
+        .collect::<eyre::Result<Vec<_>>>()?;
+    let mut repodata = Repodata::new();
+    let mut names = vec![name.to_string()];
+    while let Some(name) = names.pop() {
+        if repodata.contains_key(&name) {
+            continue;
+        }
+        let mut packages = vec![];
+        for index in &indexes {
+            packages.extend(index.packages(&name)?);
+        }
+        names.extend(
+            packages
+                .iter()
+                .flat_map(|p| &p.depends)
+                .map(|d| parse_dependency(d).name),
+        );
+        repodata.insert(name, packages);
+    }
+    Ok(repodata)
+}
+
+impl RepodataIndex {
+    /// `repodata.json.zst` of the subdir is cached for `fetch_remote_versions_cache`
+    fn fetch(channel: &str, subdir: &str) -> eyre::Result<Self> {
+        let url = format!("https://conda.anaconda.org/{channel}/{subdir}");
+        let dir = dirs::CACHE.join("conda").join(channel).join(subdir);
+        let repodata_path = dir.join("repodata.json.zst");
+        let fresh = match SETTINGS.fetch_remote_versions_cache() {
+            Some(duration) => file::modified_duration(&repodata_path).is_ok_and(|d| d < duration),
+            None => repodata_path.exists(),
+        };
+        if !fresh {
+            let tmp = repodata_path.with_extension("zst.part");
+            HTTP.download_file(format!("{url}/repodata.json.zst"), &tmp, None)?;
+            file::rename(&tmp, &repodata_path)?;
+        }
+        let mut index = Self {
+            url,
+            dir,
+            repodata_path,
+            names: HashSet::new(),
+            repodata: OnceCell::new(),
+        };
+        index.names = index
+            .cache::<HashSet<String>>("names")
+            .get_or_try_init(|| Ok(index.repodata()?.keys().cloned().collect()))?
+            .clone();
+        Ok(index)
+    }
+
+    fn packages(&self, name: &str) -> eyre::Result<Vec<CondaPackage>> {
+        // virtual packages like `__glibc` as well
+        if !self.names.contains(name) {
+            return Ok(vec![]);
+        }
+        let cache = self.cache(&format!("packages/{name}"));
+        let packages = cache
+            .get_or_try_init(|| Ok(self.repodata()?.get(name).cloned().unwrap_or_default()))?;
+        Ok(packages.clone())
+    }
+
+    /// the cache files are stale once the repodata is downloaded again
+    fn cache<T: serde::Serialize + DeserializeOwned>(&self, name: &str) -> CacheManager<T> {
+        CacheManagerBuilder::new(self.dir.join(format!("{name}.msgpack.z")))
+            .with_fresh_file(self.repodata_path.clone())
+            .build()
+    }
+
+    /// parses all of `repodata.json`, this is only needed for packages that aren't cached yet
+    fn repodata(&self) -> eyre::Result<&Repodata> {
+        self.repodata.get_or_try_init(|| {
+            let path = &self.repodata_path;
+            let json: RepodataJson = serde_json::from_slice(&zstd::decode_all(file::open(path)?)?)
+                .wrap_err_with(|| format!("failed to parse {}", file::display_path(path)))?;
+            let mut repodata = Repodata::new();
+            for (filename, mut pkg) in json.packages.into_iter().chain(json.packages_conda) {
+                pkg.url = format!("{}/{filename}", self.url);
+                repodata.entry(pkg.name.clone()).or_default().push(pkg);
+            }
+            Ok(repodata)
+        })
+    }
+}
+
+/// the packages matching all of `deps`, newest first. The highest build number wins between
+/// builds of the same version and `.conda` wins over `.tar.bz2`.
+fn find_packages<'a>(
+    repodata: &'a Repodata,
+    name: &str,
+    deps: &[&Dependency],
+) -> Vec<&'a CondaPackage> {
+    repodata
+        .get(name)
+        .into_iter()
+        .flatten()
+        .filter(|p| deps.iter().all(|d| d.matches(p)))
+        .sorted_by(|a, b| {
+            compare_versions(&b.version, &a.version)
+                .then(b.build_number.cmp(&a.build_number))
+                .then(b.url.ends_with(".conda").cmp(&a.url.ends_with(".conda")))
+        })
+        .collect()
+}
+
+/// the package at `version` and its dependencies by name, the package itself first.
+/// Packages are picked newest first, backtracking to older ones when they conflict with a
+/// requirement or constraint of another package or with `virtual_packages` of the system.
+fn resolve<'a>(
+    repodata: &'a Repodata,
+    name: &str,
+    version: &str,
+    virtual_packages: &'a [CondaPackage],
+) -> eyre::Result<IndexMap<String, CondaPackage>> {
+    let root = parse_dependency(&format!("{name} =={version}"));
+    let mut resolver = Resolver {
+        repodata,
+        constrains: vec![],
+        steps: 0,
+        conflict: None,
+    };
+    let mut packages = virtual_packages
+        .iter()
+        .map(|p| (p.name.clone(), p))
+        .collect();
+    let mut reqs = vec![(name.to_string(), root)];
+    if !resolver.solve(&mut packages, &mut reqs)? {
+        let (_, conflict) = resolver.conflict.unwrap_or_default();
+        bail!("failed to resolve {name} {version}: {conflict}");
+    }
+    Ok(packages
+        .into_iter()
+        .filter(|(name, _)| !name.starts_with("__"))
+        .map(|(name, pkg)| (name, pkg.clone()))
+        .collect())
+}
+
+struct Resolver<'a> {
+    repodata: &'a Repodata,
+    /// `constrains` of the resolved packages (with the name of the package constraining them)
+    constrains: Vec<(String, Dependency)>,
+    steps: usize,
+    /// the conflict found with the most packages resolved, reported if there is no solution
+    conflict: Option<(usize, String)>,
+}
+
+impl<'a> Resolver<'a> {
+    /// gives up on dependency trees that keep conflicting instead of trying every combination
+    const MAX_STEPS: usize = 10_000;
+
+    /// resolves the requirements in `reqs` (with the name of the package requiring them) that
+    /// aren't in `packages` yet, returns false if they can't be resolved
+    fn solve(
+        &mut self,
+        packages: &mut IndexMap<String, &'a CondaPackage>,
+        reqs: &mut Vec<(String, Dependency)>,
+    ) -> eyre::Result<bool> {
+        self.steps += 1;
+        if self.steps > Self::MAX_STEPS {
+            let (_, conflict) = self.conflict.clone().unwrap_or_default();
+            bail!(
+                "gave up after trying {} packages: {conflict}",
+                Self::MAX_STEPS
+            );
+        }
+        let names = reqs
+            .iter()
+            .map(|(_, d)| &d.name)
+            // virtual packages like `__glibc` are provided by the system, the ones it has are
+            // already in `packages`
+            .filter(|name| !name.starts_with("__") && !packages.contains_key(*name))
+            .unique()
+            .cloned()
+            .collect_vec();
+        if names.is_empty() {
+            return Ok(true);
+        }
+        // the most constrained package first so conflicts are found early
+        let mut next: Option<(String, Vec<&'a CondaPackage>)> = None;
+        for name in names {
+            let name_reqs = reqs
+                .iter()
+                .chain(&self.constrains)
+                .filter(|(_, d)| d.name == name)
+                .collect_vec();
+            let deps = name_reqs.iter().map(|(_, d)| d).collect_vec();
+            let candidates = find_packages(self.repodata, &name, &deps);
+            if candidates.is_empty() {
+                let reqs = name_reqs
+                    .iter()
+                    .map(|(parent, d)| format!("{d} (required by {parent})"))
+                    .join(", ");
+                self.add_conflict(packages, format!("no {} package matches {reqs}", subdir()));
+                return Ok(false);
+            }
+            if next
+                .as_ref()
+                .map_or(true, |(_, c)| candidates.len() < c.len())
+            {
+                next = Some((name, candidates));
+            }
+        }
+        let (name, candidates) = next.unwrap();
+        for pkg in candidates {
+            let deps = pkg
+                .depends
+                .iter()
+                .map(|d| parse_dependency(d))
+                .collect_vec();
+            let constrains = pkg
+                .constrains
+                .iter()
+                .map(|d| parse_dependency(d))
+                .collect_vec();
+            let conflict = deps.iter().chain(&constrains).find_map(|d| {
+                packages
+                    .get(&d.name)
+                    .filter(|p| !d.matches(p))
+                    .map(|p| (d, p))
+            });
+            if let Some((dep, p)) = conflict {
+                let msg = if p.name.starts_with("__") {
+                    format!(
+                        "{} {} {} requires {dep} but the system has {} {}",
+                        pkg.name, pkg.version, pkg.build, p.name, p.version
+                    )
+                } else {
+                    format!(
+                        "{} {} {} requires {dep} but {} {} {} is required by another package",
+                        pkg.name, pkg.version, pkg.build, p.name, p.version, p.build
+                    )
+                };
+                self.add_conflict(packages, msg);
+                continue;
+            }
+            let (len, constrains_len) = (reqs.len(), self.constrains.len());
+            reqs.extend(deps.into_iter().map(|d| (pkg.name.clone(), d)));
+            self.constrains
+                .extend(constrains.into_iter().map(|d| (pkg.name.clone(), d)));
+            packages.insert(name.clone(), pkg);
+            if self.solve(packages, reqs)? {
+                return Ok(true);
+            }
+            packages.pop();
+            reqs.truncate(len);
+            self.constrains.truncate(constrains_len);
+        }
+        Ok(false)
+    }
+
+    fn add_conflict(&mut self, packages: &IndexMap<String, &'a CondaPackage>, msg: String) {
+        if self
+            .conflict
+            .as_ref()
+            .map_or(true, |(n, _)| packages.len() > *n)
+        {
+            self.conflict = Some((packages.len(), msg));
+        }
+    }
+}
+
+/// the packages locked for `name`, looked up by their url instead of resolving them again
+fn locked_packages(
+    repodata: &Repodata,
+    name: &str,
+    locked: &LockfilePlatform,
+) -> eyre::Result<IndexMap<String, CondaPackage>> {
+    let by_url: HashMap<&str, &CondaPackage> = repodata
+        .values()
+        .flatten()
+        .map(|p| (p.url.as_str(), p))
+        .collect();
+    [(name, locked)]
+        .into_iter()
+        .chain(locked.dependencies.iter().map(|(n, p)| (n.as_str(), p)))
+        .map(|(name, p)| {
+            let url = p.url.as_deref().unwrap_or_default();
+            match by_url.get(url) {
+                Some(pkg) => Ok((name.to_string(), (*pkg).clone())),
+                None => bail!("locked package {name} {url} not found in the channel"),
+            }
+        })
+        .collect()
+}
+
+/// `python_abi 3.12.* *_cp312` to its name, version spec and build string
+fn parse_dependency(dep: &str) -> Dependency {
+    let mut parts = dep.split_whitespace();
+    Dependency {
+        name: parts.next().unwrap_or_default().to_string(),
+        version: parts.next().unwrap_or("*").to_string(),
+        build: parts.next().unwrap_or("*").to_string(),
+    }
+}
+
+/// build strings are matched exactly or as globs, e.g.: `*_cp312`
+fn matches_build(build: &str, pattern: &str) -> bool {
+    let re = format!("^{}$", regex::escape(pattern).replace(r"\*", ".*"));
+    regex::Regex::new(&re).is_ok_and(|re| re.is_match(build))
+}
+
+/// conda version specs, e.g.: `>=1.2,<2.0a0`, `3.12.*` or `1.2|1.3`
+fn matches_spec(version: &str, spec: &str) -> bool {
+    spec.split('|').any(|alt| {
+        alt.split(',')
+            .all(|c| matches_constraint(version, c.trim()))
+    })
+}
+
+fn matches_constraint(version: &str, constraint: &str) -> bool {
+    if constraint.is_empty() || constraint == "*" {
+        return true;
+    }
+    for op in [">=", "<=", "==", "!=", "~=", ">", "<", "="] {
+        let Some(v) = constraint.strip_prefix(op) else {
+            continue;
+        };
+        let ord = compare_versions(version, v);
+        return match op {
+            ">=" => ord.is_ge(),
+            "<=" => ord.is_le(),
+            ">" => ord.is_gt(),
+            "<" => ord.is_lt(),
+            "==" => ord.is_eq(),
+            "!=" => !ord.is_eq(),
+            "~=" => {
+                let prefix = v.rsplit_once('.').map(|(p, _)| p).unwrap_or(v);
+                ord.is_ge() && matches_constraint(version, &format!("{prefix}.*"))
+            }
+            // `=1.2` is the same as `1.2.*`
+            _ => matches_constraint(version, &format!("{}.*", v.trim_end_matches(".*"))),
+        };
+    }
+    match constraint.strip_suffix('*') {
+        Some(prefix) => {
+            let prefix = prefix.trim_end_matches('.');
+            version == prefix || version.starts_with(&format!("{prefix}."))
+        }
+        None => compare_versions(version, constraint).is_eq(),
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum VersionPart {
+    // strings sort before numbers so `1.0a0` is older than `1.0`
+    Str(String),
+    Num(u64),
+}
+
+/// compares versions like conda, missing parts are 0 so `1.0` and `1.0.0` are equal
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    let parts = |v: &str| {
+        regex!(r"\d+|[a-zA-Z]+")
+            .find_iter(&v.to_lowercase())
+            .map(|m| match m.as_str().parse() {
+                Ok(n) => VersionPart::Num(n),
+                Err(_) => VersionPart::Str(m.as_str().to_string()),
+            })
+            .collect_vec()
+    };
+    let (a, b) = (parts(a), parts(b));
+    let zero = VersionPart::Num(0);
+    (0..a.len().max(b.len()))
+        .map(|i| a.get(i).unwrap_or(&zero).cmp(b.get(i).unwrap_or(&zero)))
+        .find(|o| o.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+/// `.conda` packages are zips of zstd tarballs, `.tar.bz2` packages are tarballs
+fn extract_package(tarball: &Path, dest: &Path) -> eyre::Result<()> {
+    if tarball.extension().is_some_and(|ext| ext == "conda") {
+        let zip_dir = dest.with_extension("zip");
+        file::remove_all(&zip_dir)?;
+        file::unzip(tarball, &zip_dir)?;
+        for f in file::ls(&zip_dir)? {
+            if f.to_string_lossy().ends_with(".tar.zst") {
+                file::extract(&f, dest)?;
+            }
+        }
+        file::remove_all(&zip_dir)?;
+        Ok(())
+    } else {
+        file::extract(tarball, dest)
+    }
+}
+
+fn read_paths(dir: &Path) -> eyre::Result<Vec<CondaPath>> {
+    let paths_json = dir.join("info").join("paths.json");
+    if !paths_json.exists() {
+        return Ok(vec![]);
+    }
+    let paths: CondaPaths = serde_json::from_str(&file::read_to_string(&paths_json)?)
+        .wrap_err_with(|| format!("failed to parse {}", file::display_path(&paths_json)))?;
+    Ok(paths.paths)
+}
+
+fn read_link(dir: &Path) -> eyre::Result<CondaLink> {
+    let link_json = dir.join("info").join("link.json");
+    if !link_json.exists() {
+        return Ok(CondaLink::default());
+    }
+    serde_json::from_str(&file::read_to_string(&link_json)?)
+        .wrap_err_with(|| format!("failed to parse {}", file::display_path(&link_json)))
+}
+
+/// where the `site-packages` and `python-scripts` of noarch python packages are installed
+fn noarch_python_dirs(python_version: &str) -> (String, &'static str) {
+    if cfg!(windows) {
+        ("Lib/site-packages".to_string(), "Scripts")
+    } else {
+        (format!("lib/python{python_version}/site-packages"), "bin")
+    }
+}
+
+fn noarch_python_path(path: &str, site_packages: &str, scripts: &str) -> String {
+    if let Some(rest) = path.strip_prefix("site-packages/") {
+        format!("{site_packages}/{rest}")
+    } else if let Some(rest) = path.strip_prefix("python-scripts/") {
+        format!("{scripts}/{rest}")
+    } else {
+        path.to_string()
+    }
+}
+
+/// writes a script running the function of an entry point like conda,
+/// e.g.: `black = black:patched_main`
+fn write_entry_point(install_path: &Path, entry_point: &str) -> eyre::Result<()> {
+    if cfg!(windows) {
+        bail!("entry points of noarch python packages are not supported on windows");
+    }
+    let (cmd, target) = entry_point
+        .split_once('=')
+        .ok_or_else(|| eyre!("invalid entry point: {entry_point}"))?;
+    let (module, func) = target
+        .trim()
+        .split_once(':')
+        .ok_or_else(|| eyre!("invalid entry point: {entry_point}"))?;
+    let import = func.split('.').next().unwrap_or(func);
+    let python = install_path.join("bin").join("python");
+    let script = install_path.join("bin").join(cmd.trim());
+    file::write(
+        &script,
+        formatdoc! {r#"
+            #!{python}
+            # -*- coding: utf-8 -*-
+            import re
+            import sys
+
+            from {module} import {import}
+
+            if __name__ == '__main__':
+                sys.argv[0] = re.sub(r'(-script\.pyw?|\.exe)?$', '', sys.argv[0])
+                sys.exit({func}())
+            "#, python = python.display()},
+    )?;
+    file::make_executable(&script)
+}
+
+/// packages are built in a long placeholder prefix which is replaced with the install path,
+/// strings in binaries are padded with nul bytes to keep their length
+fn replace_prefix(path: &Path, placeholder: &str, prefix: &Path, binary: bool) -> eyre::Result<()> {
+    let data = std::fs::read(path)?;
+    let prefix = prefix.to_string_lossy();
+    let replaced = if binary {
+        if prefix.len() > placeholder.len() {
+            bail!(
+                "install path {prefix} is longer than the placeholder in {}",
+                file::display_path(path)
+            );
+        }
+        let re = Regex::new(&format!(
+            r"(?-u){}([^\x00]*)\x00",
+            regex::escape(placeholder)
+        ))?;
+        re.replace_all(&data, |caps: &Captures| {
+            let mut s = [prefix.as_bytes(), &caps[1]].concat();
+            s.resize(caps[0].len(), 0);
+            s
+        })
+    } else {
+        let re = Regex::new(&regex::escape(placeholder))?;
+        re.replace_all(&data, NoExpand(prefix.as_bytes()))
+    };
+    if replaced != data {
+        file::write(path, replaced)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_compare_versions() {
+        assert_eq!(compare_versions("1.0", "1.0.0"), Ordering::Equal);
+        assert_eq!(compare_versions("1.10.0", "1.9.2"), Ordering::Greater);
+        assert_eq!(compare_versions("2.0a0", "2.0"), Ordering::Less);
+        assert_eq!(compare_versions("3.0.a0", "2.9.9"), Ordering::Greater);
+    }
+
+    #[test]
+    fn test_matches_spec() {
+        assert!(matches_spec("1.3.1", ">=1.3.1,<2.0a0"));
+        assert!(!matches_spec("2.0.0", ">=1.3.1,<2.0a0"));
+        assert!(matches_spec("3.12.4", "3.12.*"));
+        assert!(!matches_spec("3.1.2", "3.12.*"));
+        assert!(matches_spec("1.3", "1.2|1.3"));
+        assert!(matches_spec("1.2.5", "=1.2"));
+        assert!(matches_spec("1.2.5", "~=1.2.0"));
+        assert!(!matches_spec("1.3.0", "~=1.2.0"));
+        assert!(matches_spec("7.0.1", "*"));
+    }
+
+    #[test]
+    fn test_parse_dependency() {
+        let dep = parse_dependency("python_abi 3.12.* *_cp312");
+        assert_eq!(dep.name, "python_abi");
+        assert_eq!(dep.version, "3.12.*");
+        assert_eq!(dep.build, "*_cp312");
+        assert_eq!(parse_dependency("libzlib >=1.3.1,<2.0a0").build, "*");
+        assert!(matches_build("0_cp312", "*_cp312"));
+        assert!(!matches_build("0_cp311", "*_cp312"));
+        assert!(matches_build("h4c12d27_104", "h4c12d27_104"));
+    }
+
+    fn pkg(name: &str, version: &str, build: &str, depends: &[&str]) -> CondaPackage {
+        CondaPackage {
+            name: name.to_string(),
+            version: version.to_string(),
+            build: build.to_string(),
+            build_number: 0,
+            depends: depends.iter().map(|d| d.to_string()).collect(),
+            constrains: vec![],
+            sha256: None,
+            url: format!(
+                "https://conda.anaconda.org/conda-forge/noarch/{name}-{version}-{build}.conda"
+            ),
+        }
+    }
+
+    #[test]
+    fn test_resolve() {
+        let repodata: Repodata = [
+            pkg(
+                "tool",
+                "1.0",
+                "0",
+                &["python >=3.11", "python_abi 3.12.* *_cp312", "lib"],
+            ),
+            pkg("tool", "2.0", "0", &["python 3.12.*", "lib <2"]),
+            pkg(
+                "python",
+                "3.12.4",
+                "0",
+                &["lib >=2", "python_abi 3.12.* *_cp312"],
+            ),
+            pkg("python", "3.13.0", "0", &["python_abi 3.13.* *_cp313"]),
+            pkg("python_abi", "3.12", "4_cp312", &[]),
+            pkg("python_abi", "3.13", "5_cp313", &[]),
+            pkg("lib", "1.0", "0", &[]),
+            pkg("lib", "2.0", "0", &[]),
+            // the newest util conflicts with every other
+            pkg("app", "1.0", "0", &["util", "other"]),
+            pkg("util", "2.0", "0", &["lib <2"]),
+            pkg("util", "1.0", "0", &["lib >=2"]),
+            pkg("other", "2.0", "0", &["lib >=2"]),
+            pkg("other", "1.0", "0", &["lib >=2"]),
+        ]
+        .into_iter()
+        .into_group_map_by(|p| p.name.clone());
+
+        let packages = resolve(&repodata, "tool", "1.0", &[]).unwrap();
+        assert_eq!(
+            packages.keys().collect_vec(),
+            ["tool", "python_abi", "python", "lib"]
+        );
+        assert_eq!(packages["python"].version, "3.12.4");
+        assert_eq!(packages["python_abi"].build, "4_cp312");
+        assert_eq!(packages["lib"].version, "2.0");
+
+        let packages = resolve(&repodata, "app", "1.0", &[]).unwrap();
+        assert_eq!(packages["util"].version, "1.0");
+        assert_eq!(packages["other"].version, "2.0");
+        assert_eq!(packages["lib"].version, "2.0");
+
+        let err = resolve(&repodata, "tool", "2.0", &[]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "failed to resolve tool 2.0: no linux-64 package matches lib <2 * (required by tool), lib >=2 * (required by python)"
+                .replace("linux-64", subdir())
+        );
+    }
+
+    #[test]
+    fn test_resolve_constrains() {
+        let constrained = |name, version, build, depends, constrains: &[&str]| CondaPackage {
+            constrains: constrains.iter().map(|d| d.to_string()).collect(),
+            ..pkg(name, version, build, depends)
+        };
+        let repodata: Repodata = [
+            pkg("tool", "1.0", "0", &["lib", "plugin"]),
+            constrained("lib", "2.0", "0", &[], &["plugin >=2"]),
+            constrained("lib", "1.0", "0", &[], &["plugin <2"]),
+            pkg("plugin", "2.0", "0", &["__glibc >=2.28"]),
+            pkg("plugin", "1.0", "0", &["__glibc >=2.17"]),
+            // constrains don't make `other` a dependency of lib
+            constrained("app", "1.0", "0", &["lib"], &["other >=1"]),
+        ]
+        .into_iter()
+        .into_group_map_by(|p| p.name.clone());
+
+        let packages = resolve(&repodata, "tool", "1.0", &[]).unwrap();
+        assert_eq!(packages["lib"].version, "2.0");
+        assert_eq!(packages["plugin"].version, "2.0");
+
+        // plugin 2.0 needs a newer glibc so lib has to be downgraded to allow plugin 1.0
+        let glibc = [virtual_package("__glibc", "2.17")];
+        let packages = resolve(&repodata, "tool", "1.0", &glibc).unwrap();
+        assert_eq!(packages.keys().collect_vec(), ["tool", "lib", "plugin"]);
+        assert_eq!(packages["lib"].version, "1.0");
+        assert_eq!(packages["plugin"].version, "1.0");
+
+        let glibc = [virtual_package("__glibc", "2.12")];
+        let err = resolve(&repodata, "tool", "1.0", &glibc).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "failed to resolve tool 1.0: plugin 2.0 0 requires __glibc >=2.28 * but the system has __glibc 2.12"
+        );
+
+        let packages = resolve(&repodata, "app", "1.0", &[]).unwrap();
+        assert_eq!(packages.keys().collect_vec(), ["app", "lib"]);
+    }
+
+    #[test]
+    fn test_fetch_repodata() {
+        let dir = dirs::CACHE.join("conda").join("mise-test");
+        file::remove_all(&dir).unwrap();
+        let write = |subdir: &str, repodata: serde_json::Value| {
+            let path = dir.join(subdir).join("repodata.json.zst");
+            file::create_dir_all(path.parent().unwrap()).unwrap();
+            let json = serde_json::to_vec(&repodata).unwrap();
+            file::write(&path, zstd::encode_all(&json[..], 0).unwrap()).unwrap();
+        };
+        write(
+            subdir(),
+            serde_json::json!({"packages.conda": {
+                "tool-1.0-0.conda": {"name": "tool", "version": "1.0", "build": "0", "depends": ["lib"]},
+                "lib-1.0-0.conda": {"name": "lib", "version": "1.0", "build": "0", "depends": ["__glibc"]},
+                "other-1.0-0.conda": {"name": "other", "version": "1.0", "build": "0"},
+            }}),
+        );
+        write(
+            "noarch",
+            serde_json::json!({"packages": {
+                "lib-2.0-0.tar.bz2": {"name": "lib", "version": "2.0", "build": "0"},
+            }}),
+        );
+
+        let repodata = fetch_repodata("mise-test", "tool").unwrap();
+        assert_eq!(
+            repodata.keys().sorted().collect_vec(),
+            ["__glibc", "lib", "tool"]
+        );
+        assert_eq!(
+            repodata["lib"]
+                .iter()
+                .map(|p| p.url.clone())
+                .sorted()
+                .collect_vec(),
+            [
+                format!(
+                    "https://conda.anaconda.org/mise-test/{}/lib-1.0-0.conda",
+                    subdir()
+                ),
+                "https://conda.anaconda.org/mise-test/noarch/lib-2.0-0.tar.bz2".to_string(),
+            ]
+        );
+        // read from the cache of each package the next time
+        let index = RepodataIndex::fetch("mise-test", subdir()).unwrap();
+        assert!(index
+            .cache::<Vec<CondaPackage>>("packages/tool")
+            .get()
+            .is_some());
+        assert!(index
+            .cache::<Vec<CondaPackage>>("packages/other")
+            .get()
+            .is_none());
+        assert_eq!(index.packages("tool").unwrap()[0].depends, ["lib"]);
+        assert!(index.repodata.get().is_none());
+        file::remove_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_locked_packages() {
+        let repodata: Repodata = [pkg("tool", "1.0", "0", &[]), pkg("tool", "2.0", "0", &[])]
+            .into_iter()
+            .into_group_map_by(|p| p.name.clone());
+        let locked = LockfilePlatform {
+            url: Some(pkg("tool", "1.0", "0", &[]).url),
+            ..Default::default()
+        };
+        let packages = locked_packages(&repodata, "tool", &locked).unwrap();
+        assert_eq!(packages.keys().collect_vec(), ["tool"]);
+        assert_eq!(packages["tool"].version, "1.0");
+    }
+
+    #[test]
+    fn test_noarch_python_path() {
+        let (site_packages, scripts) = noarch_python_dirs("3.12");
+        assert_eq!(
+            noarch_python_path("site-packages/black/__init__.py", &site_packages, scripts),
+            if cfg!(windows) {
+                "Lib/site-packages/black/__init__.py"
+            } else {
+                "lib/python3.12/site-packages/black/__init__.py"
+            }
+        );
+        assert_eq!(
+            noarch_python_path("python-scripts/black", &site_packages, scripts),
+            format!("{scripts}/black")
+        );
+        assert_eq!(
+            noarch_python_path("info/about.json", &site_packages, scripts),
+            "info/about.json"
+        );
+    }
+
+    #[test]
+    fn test_replace_prefix() {
+        let dir = tempfile::tempdir().unwrap();
+        let placeholder = "/opt/placeholder_placeholder";
+        let prefix = Path::new("/mise/tool");
+
+        let text = dir.path().join("tool-config");
+        file::write(&text, format!("prefix={placeholder}/lib\n")).unwrap();
+        replace_prefix(&text, placeholder, prefix, false).unwrap();
+        assert_eq!(
+            file::read_to_string(&text).unwrap(),
+            "prefix=/mise/tool/lib\n"
+        );
+
+        let binary = dir.path().join("tool");
+        file::write(&binary, format!("a\0{placeholder}/lib\0b")).unwrap();
+        replace_prefix(&binary, placeholder, prefix, true).unwrap();
+        // the placeholder is 18 bytes longer than the install path, plus the nul terminator
+        let expected = [&b"a\0/mise/tool/lib"[..], &[0; 19], &b"b"[..]].concat();
+        assert_eq!(std::fs::read(&binary).unwrap(), expected);
+    }
+}
@@ -0,0 +1,239 @@
+use std::fmt::Debug;
+use std::path::PathBuf;
+
+use eyre::{bail, eyre, WrapErr};
+use itertools::Itertools;
+use regex::Regex;
+use versions::Versioning;
+
+use crate::backend::{config_tool_options, Backend, BackendType};
+use crate::cache::CacheManagerBuilder;
+use crate::cli::args::BackendArg;
+use crate::config::SETTINGS;
+use crate::hash::{ensure_checksum_sha256, hash_to_str, parse_shasums};
+use crate::http::{HTTP, HTTP_FETCH};
+use crate::install_context::InstallContext;
+use crate::tera::{get_tera, BASE_CONTEXT};
+use crate::toolset::{ToolRequest, ToolVersion, ToolVersionOptions};
+use crate::{env, file, lockfile};
+
+/// installs tools from archives or binaries at a templated url, e.g.:
+///
+/// ```toml
+/// [tools]
+/// "http:terraform" = { version = "1.9.0", url = "https://releases.hashicorp.com/terraform/{{version}}/terraform_{{version}}_{{os}}_{{arch}}.zip" }
+/// ```
+#[derive(Debug)]
+pub struct HttpBackend {
+    ba: BackendArg,
+}
+
+impl Backend for HttpBackend {
+    fn get_type(&self) -> BackendType {
+        BackendType::Http
+    }
+
+    fn fa(&self) -> &BackendArg {
+        &self.ba
+    }
+
+    fn _list_remote_versions(&self) -> eyre::Result<Vec<String>> {
+        let opts = config_tool_options(&self.ba);
+        let Some(url) = opts.get("version_list_url") else {
+            return Ok(vec![]);
+        };
+        let url = render(url, "")?;
+        let regex = opts
+            .get("version_regex")
+            .map(|r| r.as_str())
+            .unwrap_or(r"v?(\d+\.\d+\.\d+)");
+        CacheManagerBuilder::new(self.ba.cache_path.join("remote_versions.msgpack.z"))
+            .with_fresh_duration(SETTINGS.fetch_remote_versions_cache())
+            .with_cache_key(hash_to_str(&(&url, regex)))
+            .build()
+            .get_or_try_init(|| {
+                let body = HTTP_FETCH.get_text(&url)?;
+                parse_versions(&body, regex)
+            })
+            .cloned()
+    }
+
+    fn install_version_impl(&self, ctx: &InstallContext) -> eyre::Result<()> {
+        SETTINGS.ensure_experimental("http backend")?;
+        let opts = ctx.tv.request.options();
+        let url = opts
+            .get("url")
+            .ok_or_else(|| eyre!("url is required for {}", self.ba))?;
+        let url = render(url, &ctx.tv.version)?;
+        let filename = url_filename(&url);
+        let file = ctx.tv.download_path().join(filename);
+
+        ctx.pr.set_message(format!("downloading {filename}"));
+        HTTP.download_file(&url, &file, Some(ctx.pr.as_ref()))?;
+
+        if let Some(checksum) = self.checksum(&opts, &ctx.tv, filename)? {
+            ctx.pr.set_message(format!("checksum {filename}"));
+            ensure_checksum_sha256(&file, &checksum, Some(ctx.pr.as_ref()))?;
+        }
+        lockfile::verify_artifact(&ctx.tv, Some(&url), &file, Some(ctx.pr.as_ref()))?;
+
+        ctx.pr.set_message(format!("installing {filename}"));
+        let install_path = ctx.tv.install_path();
+        file::remove_all(&install_path)?;
+        if file::is_archive(filename) {
+            let strip_components = match opts.get("strip_components") {
+                Some(n) => n
+                    .parse()
+                    .wrap_err_with(|| format!("invalid strip_components: {n}"))?,
+                None => 0,
+            };
+            let tmp = ctx.tv.download_path().join("extract");
+            file::remove_all(&tmp)?;
+            file::create_dir_all(&tmp)?;
+            file::extract(&file, &tmp)?;
+            file::move_stripped(&tmp, &install_path, strip_components)?;
+            file::remove_all(&tmp)?;
+        } else {
+            // a single binary
+            let bin = opts.get("bin").map(|b| b.as_str()).unwrap_or(self.name());
+            let bin = install_path.join("bin").join(bin);
+            file::create_dir_all(bin.parent().unwrap())?;
+            file::copy(&file, &bin)?;
+            file::make_executable(&bin)?;
+        }
+        Ok(())
+    }
+
+    fn list_bin_paths(&self, tv: &ToolVersion) -> eyre::Result<Vec<PathBuf>> {
+        if let ToolRequest::System(..) = tv.request {
+            return Ok(vec![]);
+        }
+        let install_path = tv.install_path();
+        let bin_path = match tv.request.options().get("bin_path") {
+            Some(bin_path) => install_path.join(render(bin_path, &tv.version)?),
+            None if install_path.join("bin").is_dir() => install_path.join("bin"),
+            None => install_path,
+        };
+        Ok(vec![bin_path])
+    }
+}
+
+impl HttpBackend {
+    pub fn from_arg(ba: BackendArg) -> Self {
+        Self { ba }
+    }
+
+    /// `checksum = "sha256:<hash>"` or the hash for `filename` in the file at `checksum_url`
+    fn checksum(
+        &self,
+        opts: &ToolVersionOptions,
+        tv: &ToolVersion,
+        filename: &str,
+    ) -> eyre::Result<Option<String>> {
+        if let Some(checksum) = opts.get("checksum") {
+            let checksum = render(checksum, &tv.version)?;
+            return match checksum.split_once(':') {
+                Some(("sha256", hash)) => Ok(Some(hash.to_string())),
+                Some((algo, _)) => bail!("unsupported checksum algorithm: {algo}"),
+                None => Ok(Some(checksum)),
+            };
+        }
+        let Some(checksum_url) = opts.get("checksum_url") else {
+            return Ok(None);
+        };
+        let checksum_url = render(checksum_url, &tv.version)?;
+        let body = HTTP.get_text(&checksum_url)?;
+        let hash = match body.split_whitespace().collect_vec().as_slice() {
+            // a file with only the hash of the artifact
+            [hash] => Some(hash.to_string()),
+            _ => parse_shasums(&body)
+                .into_iter()
+                .find(|(name, _)| name.trim_start_matches('*') == filename)
+                .map(|(_, hash)| hash),
+        };
+        match hash {
+            Some(hash) => Ok(Some(hash)),
+            None => bail!("checksum for {filename} not found in {checksum_url}"),
+        }
+    }
+}
+
+/// renders a url or option template with `version`, `os` and `arch`
+fn render(template: &str, version: &str) -> eyre::Result<String> {
+    let mut ctx = BASE_CONTEXT.clone();
+    ctx.insert("version", version);
+    ctx.insert("os", os());
+    ctx.insert("arch", arch());
+    let mut tera = get_tera(None);
+    Ok(tera.render_str(template, &ctx)?)
+}
+
+fn os() -> &'static str {
+    match env::consts::OS {
+        "macos" => "darwin",
+        os => os,
+    }
+}
+
+fn arch() -> &'static str {
+    match env::consts::ARCH {
+        "x86_64" => "amd64",
+        "aarch64" => "arm64",
+        arch => arch,
+    }
+}
+
+fn url_filename(url: &str) -> &str {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+fn parse_versions(body: &str, regex: &str) -> eyre::Result<Vec<String>> {
+    let re = Regex::new(regex).wrap_err_with(|| format!("invalid version_regex: {regex}"))?;
+    Ok(re
+        .captures_iter(body)
+        .filter_map(|c| c.get(1).or_else(|| c.get(0)))
+        .map(|m| m.as_str().to_string())
+        .unique()
+        .sorted_by_cached_key(|v| (Versioning::new(v), v.to_string()))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let url = "https://releases.hashicorp.com/terraform/{{version}}/terraform_{{version}}_{{os}}_{{arch}}.zip";
+        assert_eq!(
+            render(url, "1.9.0").unwrap(),
+            format!(
+                "https://releases.hashicorp.com/terraform/1.9.0/terraform_1.9.0_{}_{}.zip",
+                os(),
+                arch()
+            )
+        );
+        assert_eq!(
+            url_filename("https://example.com/a/tool.tar.gz?x=1"),
+            "tool.tar.gz"
+        );
+    }
+
+    #[test]
+    fn test_parse_versions() {
+        let body = r#"<a href="/tool/1.10.0/">tool_1.10.0</a>
+<a href="/tool/1.9.2/">tool_1.9.2</a>
+<a href="/tool/1.9.2-rc1/">tool_1.9.2-rc1</a>"#;
+        assert_eq!(
+            parse_versions(body, r#"/tool/([^/]+)/"#).unwrap(),
+            vec!["1.9.2-rc1", "1.9.2", "1.10.0"]
+        );
+        assert_eq!(
+            parse_versions(body, r"\d+\.\d+\.\d+").unwrap(),
+            vec!["1.9.2", "1.10.0"]
+        );
+    }
+}
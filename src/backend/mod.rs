@@ -24,15 +24,19 @@ use crate::install_context::InstallContext;
 use crate::plugins::core::{CorePlugin, CORE_PLUGINS};
 use crate::plugins::{Plugin, PluginType, VERSION_REGEX};
 use crate::runtime_symlinks::is_runtime_symlink;
-use crate::toolset::{is_outdated_version, ToolRequest, ToolSource, ToolVersion, Toolset};
+use crate::toolset::{
+    is_outdated_version, ToolRequest, ToolSource, ToolVersion, ToolVersionOptions, Toolset,
+};
 use crate::ui::progress_report::SingleReport;
 use crate::{dirs, env, file, lock_file, versions_host};
 
 pub mod asdf;
 pub mod backend_meta;
 pub mod cargo;
+pub mod conda;
 mod external_plugin_cache;
 pub mod go;
+pub mod http;
 pub mod npm;
 pub mod pipx;
 pub mod spm;
@@ -61,8 +65,10 @@ pub type VersionCacheManager = CacheManager<Vec<String>>;
 pub enum BackendType {
     Asdf,
     Cargo,
+    Conda,
     Core,
     Go,
+    Http,
     Npm,
     Pipx,
     Spm,
@@ -152,9 +158,11 @@ pub fn arg_to_backend(ba: BackendArg) -> ABackend {
     match ba.backend_type {
         BackendType::Asdf => Arc::new(asdf::AsdfBackend::from_arg(ba)),
         BackendType::Cargo => Arc::new(cargo::CargoBackend::from_arg(ba)),
+        BackendType::Conda => Arc::new(conda::CondaBackend::from_arg(ba)),
         BackendType::Core => Arc::new(asdf::AsdfBackend::from_arg(ba)),
         BackendType::Npm => Arc::new(npm::NPMBackend::from_arg(ba)),
         BackendType::Go => Arc::new(go::GoBackend::from_arg(ba)),
+        BackendType::Http => Arc::new(http::HttpBackend::from_arg(ba)),
         BackendType::Pipx => Arc::new(pipx::PIPXBackend::from_arg(ba)),
         BackendType::Spm => Arc::new(spm::SPMBackend::from_arg(ba)),
         BackendType::Ubi => Arc::new(ubi::UbiBackend::from_arg(ba)),
@@ -162,6 +170,31 @@ pub fn arg_to_backend(ba: BackendArg) -> ABackend {
     }
 }
 
+/// options of the tool in `[tools]` for when they are needed without a tool version, e.g.: to
+/// list remote versions
+pub fn config_tool_options(ba: &BackendArg) -> ToolVersionOptions {
+    CONFIG
+        .get_tool_request_set()
+        .ok()
+        .and_then(|trs| trs.tools.get(ba))
+        .and_then(|trs| trs.first())
+        .map(|tr| tr.options())
+        .or_else(|| ba.opts.clone())
+        .unwrap_or_default()
+}
+
+/// `prerelease = true` lists prereleases of tools installed from GitHub releases
+pub fn include_prereleases(opts: &ToolVersionOptions) -> bool {
+    opts.get("prerelease").is_some_and(|p| p == "true")
+}
+
+/// a remote version and when it was released if the backend knows, see `mise ls-remote --json`
+#[derive(Debug, Default, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct VersionInfo {
+    pub version: String,
+    pub created_at: Option<String>,
+}
+
 impl From<BackendArg> for ABackend {
     fn from(fa: BackendArg) -> Self {
         get(&fa)
@@ -239,6 +272,17 @@ pub trait Backend: Debug + Send + Sync {
             .cloned()
     }
     fn _list_remote_versions(&self) -> eyre::Result<Vec<String>>;
+    /// remote versions with their release dates, backends that don't know them only have versions
+    fn list_remote_versions_with_info(&self) -> eyre::Result<Vec<VersionInfo>> {
+        Ok(self
+            .list_remote_versions()?
+            .into_iter()
+            .map(|version| VersionInfo {
+                version,
+                ..Default::default()
+            })
+            .collect())
+    }
     fn latest_stable_version(&self) -> eyre::Result<Option<String>> {
         self.latest_version(Some("latest".into()))
     }
@@ -1,8 +1,9 @@
-use crate::backend::{Backend, BackendType};
+use crate::backend::{config_tool_options, include_prereleases, Backend, BackendType};
 use crate::cache::{CacheManager, CacheManagerBuilder};
 use crate::cli::args::BackendArg;
 use crate::cmd::CmdLineRunner;
 use crate::config::{Config, SETTINGS};
+use crate::forge::ForgeRelease;
 use crate::github;
 use crate::http::HTTP_FETCH;
 use crate::install_context::InstallContext;
@@ -54,8 +55,15 @@ impl Backend for PIPXBackend {
                 }
                 PipxRequest::Git(url) if url.starts_with("https://github.com/") => {
                     let repo = url.strip_prefix("https://github.com/").unwrap();
+                    let prerelease = include_prereleases(&config_tool_options(&self.ba));
                     let data = github::list_releases(repo)?;
-                    Ok(data.into_iter().rev().map(|r| r.tag_name).collect())
+                    Ok(data
+                        .into_iter()
+                        .map(ForgeRelease::from)
+                        .filter(|r| r.is_listed(prerelease))
+                        .rev()
+                        .map(|r| r.tag_name)
+                        .collect())
                 }
                 PipxRequest::Git { .. } => Ok(vec!["latest".to_string()]),
             })
@@ -1,8 +1,9 @@
-use crate::backend::{Backend, BackendType};
+use crate::backend::{config_tool_options, include_prereleases, Backend, BackendType};
 use crate::cache::{CacheManager, CacheManagerBuilder};
 use crate::cli::args::BackendArg;
 use crate::cmd::CmdLineRunner;
 use crate::config::{Settings, SETTINGS};
+use crate::forge::ForgeRelease;
 use crate::install_context::InstallContext;
 use crate::{file, github};
 use eyre::WrapErr;
@@ -43,10 +44,13 @@ impl Backend for SPMBackend {
 
     fn _list_remote_versions(&self) -> eyre::Result<Vec<String>> {
         let repo = SwiftPackageRepo::new(self.name())?;
+        let prerelease = include_prereleases(&config_tool_options(&self.ba));
         self.remote_version_cache
             .get_or_try_init(|| {
                 Ok(github::list_releases(repo.shorthand.as_str())?
                     .into_iter()
+                    .map(ForgeRelease::from)
+                    .filter(|r| r.is_listed(prerelease))
                     .map(|r| r.tag_name)
                     .rev()
                     .collect())
@@ -1,23 +1,28 @@
 use std::fmt::Debug;
 
-use crate::backend::{Backend, BackendType};
-use crate::cache::{CacheManager, CacheManagerBuilder};
+use crate::backend::{config_tool_options, include_prereleases, Backend, BackendType, VersionInfo};
+use crate::cache::CacheManagerBuilder;
 use crate::cli::args::BackendArg;
+use crate::cli::version::{ARCH, OS};
 use crate::config::SETTINGS;
 use crate::env::GITHUB_TOKEN;
-use crate::github;
+use crate::forge::{Forge, ForgeRelease};
+use crate::hash::hash_to_str;
+use crate::http::HTTP;
 use crate::install_context::InstallContext;
 use crate::plugins::VERSION_REGEX;
 use crate::toolset::ToolRequest;
-use eyre::bail;
+use crate::{file, github, lockfile};
+use eyre::{bail, eyre};
+use itertools::Itertools;
 use regex::Regex;
 use ubi::UbiBuilder;
+use walkdir::WalkDir;
 use xx::regex;
 
 #[derive(Debug)]
 pub struct UbiBackend {
     ba: BackendArg,
-    remote_version_cache: CacheManager<Vec<String>>,
 }
 
 // Uses ubi for installations https://github.com/houseabsolute/ubi
@@ -35,27 +40,56 @@ impl Backend for UbiBackend {
     }
 
     fn _list_remote_versions(&self) -> eyre::Result<Vec<String>> {
+        Ok(self
+            .list_remote_versions_with_info()?
+            .into_iter()
+            .map(|v| v.version)
+            .collect())
+    }
+
+    fn list_remote_versions_with_info(&self) -> eyre::Result<Vec<VersionInfo>> {
         if name_is_url(self.name()) {
-            Ok(vec!["latest".to_string()])
-        } else {
-            self.remote_version_cache
-                .get_or_try_init(|| {
-                    Ok(github::list_releases(self.name())?
+            return Ok(vec![VersionInfo {
+                version: "latest".to_string(),
+                ..Default::default()
+            }]);
+        }
+        let opts = config_tool_options(&self.ba);
+        let forge = Forge::parse(self.name(), &opts)?;
+        let prerelease = include_prereleases(&opts);
+        CacheManagerBuilder::new(self.ba.cache_path.join("remote_releases.msgpack.z"))
+            .with_fresh_duration(SETTINGS.fetch_remote_versions_cache())
+            .with_cache_key(hash_to_str(&(&forge, prerelease)))
+            .build()
+            .get_or_try_init(|| {
+                let releases = match &forge {
+                    Some(forge) => forge.list_releases()?,
+                    None => github::list_releases(self.name())?
                         .into_iter()
-                        .map(|r| r.tag_name)
+                        .map(ForgeRelease::from)
+                        .collect_vec(),
+                };
+                Ok(releases
+                    .into_iter()
+                    .filter(|r| r.is_listed(prerelease))
+                    .map(|r| VersionInfo {
                         // trim 'v' prefixes if they exist
-                        .map(|t| match regex!(r"^v[0-9]").is_match(&t) {
-                            true => t[1..].to_string(),
-                            false => t,
-                        })
-                        .rev()
-                        .collect())
-                })
-                .cloned()
-        }
+                        version: match regex!(r"^v[0-9]").is_match(&r.tag_name) {
+                            true => r.tag_name[1..].to_string(),
+                            false => r.tag_name,
+                        },
+                        created_at: r.published_at,
+                    })
+                    .rev()
+                    .collect())
+            })
+            .cloned()
     }
 
     fn install_version_impl(&self, ctx: &InstallContext) -> eyre::Result<()> {
+        if let Some(forge) = Forge::parse(self.name(), &ctx.tv.request.options())? {
+            return self.install_from_forge(ctx, &forge);
+        }
         let mut v = ctx.tv.version.to_string();
 
         if let Err(err) = github::get_release(self.name(), &ctx.tv.version) {
@@ -144,14 +178,65 @@ impl Backend for UbiBackend {
 
 impl UbiBackend {
     pub fn from_arg(ba: BackendArg) -> Self {
-        Self {
-            remote_version_cache: CacheManagerBuilder::new(
-                ba.cache_path.join("remote_versions.msgpack.z"),
-            )
-            .with_fresh_duration(SETTINGS.fetch_remote_versions_cache())
-            .build(),
-            ba,
-        }
+        Self { ba }
+    }
+
+    /// ubi only supports github.com so releases on other forges are downloaded here
+    fn install_from_forge(&self, ctx: &InstallContext, forge: &Forge) -> eyre::Result<()> {
+        let opts = ctx.tv.request.options();
+        let v = &ctx.tv.version;
+        let release = forge
+            .get_release(v)
+            .or_else(|_| forge.get_release(&format!("v{v}")))?;
+        let asset = release
+            .find_asset(opts.get("matching").map(|m| m.as_str()))
+            .ok_or_else(|| {
+                eyre!(
+                    "no asset for {}-{} found in {forge} release {}",
+                    *OS,
+                    *ARCH,
+                    release.name.as_ref().unwrap_or(&release.tag_name)
+                )
+            })?;
+        let file = ctx.tv.download_path().join(&asset.name);
+        ctx.pr.set_message(format!("downloading {}", asset.name));
+        HTTP.download_file(&asset.url, &file, Some(ctx.pr.as_ref()))?;
+        lockfile::verify_artifact(&ctx.tv, Some(&asset.url), &file, Some(ctx.pr.as_ref()))?;
+
+        ctx.pr.set_message(format!("installing {}", asset.name));
+        let exe = opts.get("exe").map(|e| e.as_str()).unwrap_or(forge.repo());
+        let bin_dir = ctx.tv.install_path().join("bin");
+        file::remove_all(ctx.tv.install_path())?;
+        file::create_dir_all(&bin_dir)?;
+        let bin = if file::is_archive(&asset.name) {
+            let tmp = ctx.tv.download_path().join("extract");
+            file::remove_all(&tmp)?;
+            file::create_dir_all(&tmp)?;
+            file::extract(&file, &tmp)?;
+            let found = WalkDir::new(&tmp)
+                .into_iter()
+                .filter_map(Result::ok)
+                .find(|e| {
+                    e.file_type().is_file()
+                        && e.file_name().to_string_lossy().trim_end_matches(".exe") == exe
+                })
+                .ok_or_else(|| eyre!("could not find {exe} in {}", asset.name))?;
+            let bin = bin_dir.join(found.file_name());
+            file::rename(found.path(), &bin)?;
+            file::remove_all(&tmp)?;
+            bin
+        } else {
+            let ext = if asset.name.ends_with(".exe") {
+                ".exe"
+            } else {
+                ""
+            };
+            let bin = bin_dir.join(format!("{exe}{ext}"));
+            file::rename(&file, &bin)?;
+            bin
+        };
+        file::make_executable(&bin)?;
+        Ok(())
     }
 }
 
@@ -25,6 +25,7 @@ pub struct CacheManagerBuilder {
     cache_keys: Vec<String>,
     fresh_duration: Option<Duration>,
     fresh_files: Vec<PathBuf>,
+    private: bool,
 }
 
 pub static BASE_CACHE_KEYS: Lazy<Vec<String>> = Lazy::new(|| {
@@ -46,6 +47,7 @@ impl CacheManagerBuilder {
             cache_keys: BASE_CACHE_KEYS.clone(),
             fresh_files: Vec::new(),
             fresh_duration: None,
+            private: false,
         }
     }
 
@@ -59,11 +61,23 @@ impl CacheManagerBuilder {
         self
     }
 
+    /// only the current user can read the cache file, e.g.: for command output that may hold tokens
+    pub fn with_private(mut self) -> Self {
+        self.private = true;
+        self
+    }
+
     pub fn with_cache_key(mut self, key: String) -> Self {
         self.cache_keys.push(key);
         self
     }
 
+    /// leaves the mise version, os and arch out of the key, e.g.: for a cache shared between machines
+    pub fn without_base_cache_keys(mut self) -> Self {
+        self.cache_keys.retain(|k| !BASE_CACHE_KEYS.contains(k));
+        self
+    }
+
     fn cache_key(&self) -> String {
         hash_to_str(&self.cache_keys).chars().take(5).collect()
     }
@@ -81,6 +95,7 @@ impl CacheManagerBuilder {
             cache: Box::new(OnceCell::new()),
             fresh_files: self.fresh_files,
             fresh_duration: self.fresh_duration,
+            private: self.private,
         }
     }
 }
@@ -93,6 +108,7 @@ where
     cache_file_path: PathBuf,
     fresh_duration: Option<Duration>,
     fresh_files: Vec<PathBuf>,
+    private: bool,
     cache: Box<OnceCell<T>>,
 }
 
@@ -123,6 +139,20 @@ where
         Ok(val)
     }
 
+    /// the cached value if the cache file is fresh, without fetching a new one
+    pub fn get(&self) -> Option<&T> {
+        if !self.is_fresh() {
+            return None;
+        }
+        self.cache
+            .get_or_try_init(|| self.parse())
+            .map_err(|err| {
+                let path = display_path(&self.cache_file_path);
+                warn!("failed to parse cache file: {path} {err:#}");
+            })
+            .ok()
+    }
+
     fn parse(&self) -> Result<T> {
         let path = &self.cache_file_path;
         trace!("reading {}", display_path(path));
@@ -140,7 +170,13 @@ where
         let partial_path = self
             .cache_file_path
             .with_extension(format!("part-{}", random_string(8)));
-        let mut zlib = ZlibEncoder::new(File::create(&partial_path)?, Compression::fast());
+        let mut options = File::options();
+        options.write(true).create_new(true);
+        if self.private {
+            #[cfg(unix)]
+            std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        }
+        let mut zlib = ZlibEncoder::new(options.open(&partial_path)?, Compression::fast());
         zlib.write_all(&rmp_serde::to_vec_named(&val)?[..])?;
         file::rename(&partial_path, &self.cache_file_path)?;
 
@@ -1,4 +1,5 @@
 use crate::cli::config::top_toml_config;
+use crate::config::Config;
 use crate::file::display_path;
 use eyre::bail;
 use std::path::PathBuf;
@@ -24,7 +25,14 @@ impl ConfigGet {
             file = top_toml_config();
         }
         if let Some(file) = file {
-            let config: toml::Value = std::fs::read_to_string(&file)?.parse()?;
+            let mut config: toml::Value = std::fs::read_to_string(&file)?.parse()?;
+            let shows_env = match &self.key {
+                Some(key) => key == "env" || key.starts_with("env."),
+                None => true,
+            };
+            if let Some(env) = config.get_mut("env").filter(|_| shows_env) {
+                redact_env(env)?;
+            }
             let mut value = &config;
             if let Some(key) = &self.key {
                 for k in key.split('.') {
@@ -53,6 +61,28 @@ impl ConfigGet {
     }
 }
 
+/// hides vars from `env._.secrets` if they are also set in the config file
+fn redact_env(env: &mut toml::Value) -> eyre::Result<()> {
+    match env {
+        toml::Value::Table(t) => {
+            let config = Config::try_get()?;
+            let redactions = config.redactions()?;
+            for (k, v) in t.iter_mut() {
+                if redactions.contains(k) {
+                    *v = toml::Value::String("[redacted]".into());
+                }
+            }
+        }
+        toml::Value::Array(a) => {
+            for env in a {
+                redact_env(env)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
 static AFTER_LONG_HELP: &str = color_print::cstr!(
     r#"<bold><underline>Examples:</underline></bold>
 
@@ -11,6 +11,9 @@ use crate::file::display_path;
 use crate::ui::table;
 
 /// List config files currently in use
+///
+/// Config files are listed in the order they are merged in, the first one has the highest
+/// precedence. Config files loaded with `extends` are listed after the config file that extends them.
 #[derive(Debug, clap::Args)]
 #[clap(verbatim_doc_comment, after_long_help = AFTER_LONG_HELP)]
 pub struct ConfigLs {
@@ -65,6 +68,15 @@ impl ConfigLs {
                     .map(|s| serde_json::Value::String(s.to_string()))
                     .collect::<Vec<serde_json::Value>>();
                 item.insert("tools".to_string(), serde_json::Value::Array(plugins));
+                if c.is_root() {
+                    item.insert("root".to_string(), serde_json::Value::Bool(true));
+                }
+                if let Some(p) = CONFIG.extended_by.get(c.get_path()) {
+                    item.insert(
+                        "extended_by".to_string(),
+                        serde_json::Value::String(p.to_string_lossy().to_string()),
+                    );
+                }
 
                 item
             })
@@ -90,7 +102,14 @@ struct Row {
 
 impl From<&dyn ConfigFile> for Row {
     fn from(cf: &dyn ConfigFile) -> Self {
-        let path = display_path(cf.get_path());
+        let mut path = display_path(cf.get_path());
+        if cf.is_root() {
+            path = format!("{path} {}", style("(root)").dim());
+        }
+        if let Some(p) = CONFIG.extended_by.get(cf.get_path()) {
+            let extended_by = format!("(extended by {})", display_path(p));
+            path = format!("{path} {}", style(extended_by).dim());
+        }
         let ts = cf.to_tool_request_set().unwrap();
         let plugins = ts.list_plugins().into_iter().join(", ");
         let tools = format_tools_cell(plugins);
@@ -98,11 +117,14 @@ impl From<&dyn ConfigFile> for Row {
     }
 }
 
-// TODO: fill this out
 static AFTER_LONG_HELP: &str = color_print::cstr!(
     r#"<bold><underline>Examples:</underline></bold>
 
     $ <bold>mise config ls</bold>
+    Path                                                      Tools
+    ~/src/proj/mise.toml (root)                               node, python
+    ~/src/shared/mise.toml (extended by ~/src/proj/mise.toml) (none)
+    ~/.config/mise/config.toml                                (none)
 "#
 );
 
@@ -112,6 +112,10 @@ impl Doctor {
             }
         }
 
+        if let Err(err) = config.env_results() {
+            self.errors.push(format!("failed to resolve env: {err}"));
+        }
+
         if !env::is_activated() && !shims_on_path() {
             let shims = style::ncyan(display_path(*dirs::SHIMS));
             if cfg!(windows) {
@@ -1,9 +1,15 @@
+use std::collections::BTreeMap;
+
 use eyre::Result;
+use itertools::Itertools;
+use serde_derive::Serialize;
 
 use crate::cli::args::ToolArg;
 use crate::config::Config;
+use crate::env;
+use crate::file::display_path;
 use crate::shell::{get_shell, ShellType};
-use crate::toolset::{InstallOptions, Toolset, ToolsetBuilder};
+use crate::toolset::{InstallOptions, ToolRequest, Toolset, ToolsetBuilder};
 
 /// Exports env vars to activate mise a single time
 ///
@@ -23,6 +29,13 @@ pub struct Env {
     /// Shell type to generate environment variables for
     #[clap(long, short, overrides_with = "json")]
     shell: Option<ShellType>,
+
+    /// Show where the value of an env var came from
+    ///
+    /// Lists every config file, dotenv file, script and tool that set or removed it
+    /// in the order they were applied, the last one wins.
+    #[clap(long, value_name = "KEY", conflicts_with = "shell")]
+    explain: Option<String>,
 }
 
 impl Env {
@@ -32,7 +45,9 @@ impl Env {
         ts.install_arg_versions(&config, &InstallOptions::new())?;
         ts.notify_if_versions_missing();
 
-        if self.json {
+        if let Some(key) = &self.explain {
+            self.output_explain(&config, ts, key)
+        } else if self.json {
             self.output_json(&config, ts)
         } else {
             self.output_shell(&config, ts)
@@ -40,7 +55,7 @@ impl Env {
     }
 
     fn output_json(&self, config: &Config, ts: Toolset) -> Result<()> {
-        let env = ts.env_with_path(config)?;
+        let env = self.env(config, ts)?;
         miseprintln!("{}", serde_json::to_string_pretty(&env)?);
         Ok(())
     }
@@ -48,13 +63,127 @@ impl Env {
     fn output_shell(&self, config: &Config, ts: Toolset) -> Result<()> {
         let default_shell = get_shell(Some(ShellType::Bash)).unwrap();
         let shell = get_shell(self.shell).unwrap_or(default_shell);
-        for (k, v) in ts.env_with_path(config)? {
+        for (k, v) in self.env(config, ts)? {
             let k = k.to_string();
             let v = v.to_string();
             miseprint!("{}", shell.set_env(&k, &v))?;
         }
         Ok(())
     }
+
+    fn output_explain(&self, config: &Config, ts: Toolset, key: &str) -> Result<()> {
+        let env_results = config.env_results()?;
+        let redact = |v: &str| env_results.redact(key, v).to_string();
+        let initial = env::PRISTINE_ENV.get(key);
+        let environment = initial.map(|v| EnvChange {
+            source: "environment".into(),
+            directive: None,
+            value: Some(redact(v)),
+        });
+        let configs = env_results
+            .provenance
+            .get(key)
+            .into_iter()
+            .flatten()
+            .map(|p| EnvChange {
+                source: display_path(&p.config),
+                directive: p.directive.clone(),
+                value: p.value.as_deref().map(redact),
+            })
+            .collect_vec();
+        let tools = tool_changes(config, &ts, key)?;
+        // tools set their env before the config but their bin dirs are put on PATH after it
+        let chain = match key == *env::PATH_KEY {
+            true => environment.into_iter().chain(configs).chain(tools),
+            false => environment.into_iter().chain(tools).chain(configs),
+        }
+        .collect_vec();
+        let value = match ts.env_with_path(config)?.remove(key) {
+            Some(v) => Some(redact(&v)),
+            None if env_results.env_remove.contains(key) => None,
+            None => initial.map(|v| redact(v)),
+        };
+        if self.json {
+            let explain = EnvExplain {
+                key: key.to_string(),
+                value,
+                chain,
+            };
+            miseprintln!("{}", serde_json::to_string_pretty(&explain)?);
+            return Ok(());
+        }
+        match &value {
+            Some(v) => miseprintln!("{key}={v}"),
+            None => miseprintln!("{key} is not set"),
+        }
+        for c in chain {
+            let change = match &c.value {
+                Some(v) => format!("{key}={v}"),
+                None => format!("unset {key}"),
+            };
+            match &c.directive {
+                Some(directive) => miseprintln!("  {}: {directive}: {change}", c.source),
+                None => miseprintln!("  {}: {change}", c.source),
+            }
+        }
+        Ok(())
+    }
+
+    /// secrets are only shown when the output is not displayed, e.g.: `eval "$(mise env)"`
+    fn env(&self, config: &Config, ts: Toolset) -> Result<BTreeMap<String, String>> {
+        let mut env = ts.env_with_path(config)?;
+        if console::user_attended() {
+            for key in config.redactions()? {
+                if let Some(v) = env.get_mut(key) {
+                    *v = "[redacted]".into();
+                }
+            }
+        }
+        Ok(env)
+    }
+}
+
+/// the env vars set by each tool and the bin dirs they put on PATH
+fn tool_changes(config: &Config, ts: &Toolset, key: &str) -> Result<Vec<EnvChange>> {
+    let mut changes = vec![];
+    for (backend, tv) in ts.list_current_installed_versions() {
+        if matches!(tv.request, ToolRequest::System(..)) {
+            continue;
+        }
+        let value = if key == *env::PATH_KEY {
+            let paths = backend.list_bin_paths(&tv)?;
+            if paths.is_empty() {
+                continue;
+            }
+            env::join_paths(paths)?.to_string_lossy().to_string()
+        } else {
+            match backend.exec_env(config, ts, &tv)?.remove(key) {
+                Some(value) => value,
+                None => continue,
+            }
+        };
+        changes.push(EnvChange {
+            source: tv.request.source().to_string(),
+            directive: Some(format!("tool {tv}")),
+            value: Some(value),
+        });
+    }
+    Ok(changes)
+}
+
+#[derive(Serialize)]
+struct EnvExplain {
+    key: String,
+    value: Option<String>,
+    chain: Vec<EnvChange>,
+}
+
+/// a config file, dotenv file, script or tool that changed the var, see `EnvProvenance`
+#[derive(Serialize)]
+struct EnvChange {
+    source: String,
+    directive: Option<String>,
+    value: Option<String>,
 }
 
 static AFTER_LONG_HELP: &str = color_print::cstr!(
@@ -64,6 +193,12 @@ static AFTER_LONG_HELP: &str = color_print::cstr!(
     $ <bold>eval "$(mise env -s zsh)"</bold>
     $ <bold>mise env -s fish | source</bold>
     $ <bold>execx($(mise env -s xonsh))</bold>
+
+    $ <bold>mise env --explain NODE_ENV</bold>
+    NODE_ENV=production
+      environment: NODE_ENV=development
+      ~/src/proj/mise.toml: NODE_ENV=test
+      ~/src/proj/mise.toml: dotenv ~/src/proj/.env: NODE_ENV=production
 "#
 );
 
@@ -138,4 +273,15 @@ mod tests {
         reset();
         assert_cli_snapshot!("env", "-J");
     }
+
+    #[test]
+    fn test_env_explain() {
+        reset();
+        assert_cli_snapshot!("env", "--explain", "TEST_ENV_VAR", @r###"
+        TEST_ENV_VAR=test-123
+          ~/config/config.toml: TEST_ENV_VAR=test-123
+        "###);
+        let stdout = assert_cli!("env", "--explain", "JDXCODE_TINY");
+        assert!(stdout.contains("tiny@3.1.0: JDXCODE_TINY=3.1.0"));
+    }
 }
@@ -12,7 +12,7 @@ use crate::env::{PATH_KEY, TERM_WIDTH, __MISE_DIFF};
 use crate::env_diff::{EnvDiff, EnvDiffOperation};
 use crate::shell::{get_shell, ShellType};
 use crate::toolset::{Toolset, ToolsetBuilder};
-use crate::{env, hook_env};
+use crate::{env, hook_env, secrets};
 
 /// [internal] called by activate hook to update env vars directory change
 #[derive(Debug, clap::Args)]
@@ -57,6 +57,7 @@ impl HookEnv {
         patches.extend(self.build_path_operations(&settings, &paths, &__MISE_DIFF.path)?);
         patches.push(self.build_diff_operation(&diff)?);
         patches.push(self.build_watch_operation(&watch_files)?);
+        patches.extend(secrets::build_session_operation()?);
 
         let output = hook_env::build_env_commands(&*shell, &patches);
         miseprint!("{output}")?;
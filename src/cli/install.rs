@@ -38,6 +38,11 @@ pub struct Install {
     #[clap(long, overrides_with = "jobs")]
     raw: bool,
 
+    /// Require versions to come from mise.lock and fail instead of updating it
+    /// Configure with `locked` config or `MISE_LOCKED` env var
+    #[clap(long, verbatim_doc_comment)]
+    locked: bool,
+
     /// Show installation output
     ///
     /// This argument will print plugin output such as download, configuration, and compilation output.
@@ -75,7 +80,7 @@ impl Install {
             force: self.force,
             jobs: self.jobs,
             raw: self.raw,
-            latest_versions: true,
+            latest_versions: !self.locked,
         }
     }
 
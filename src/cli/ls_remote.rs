@@ -1,6 +1,7 @@
 use std::sync::Arc;
 
 use eyre::Result;
+use indexmap::IndexMap;
 use itertools::Itertools;
 use rayon::prelude::*;
 
@@ -29,6 +30,10 @@ pub struct LsRemote {
     /// same as the first argument after the "@"
     #[clap(verbatim_doc_comment)]
     pub prefix: Option<String>,
+
+    /// Output in JSON format, includes the release date of versions if the backend provides it
+    #[clap(short = 'J', long, verbatim_doc_comment)]
+    pub json: bool,
 }
 
 impl LsRemote {
@@ -52,17 +57,21 @@ impl LsRemote {
             _ => self.prefix.clone(),
         };
 
-        let versions = plugin.list_remote_versions()?;
+        let versions = plugin.list_remote_versions_with_info()?;
         let versions = match prefix {
             Some(prefix) => versions
                 .into_iter()
-                .filter(|v| v.starts_with(&prefix))
+                .filter(|v| v.version.starts_with(&prefix))
                 .collect(),
             None => versions,
         };
 
-        for version in versions {
-            miseprintln!("{}", version);
+        if self.json {
+            miseprintln!("{}", serde_json::to_string_pretty(&versions)?);
+            return Ok(());
+        }
+        for v in versions {
+            miseprintln!("{}", v.version);
         }
 
         Ok(())
@@ -72,16 +81,24 @@ impl LsRemote {
         let versions = backend::list()
             .into_par_iter()
             .map(|p| {
-                let versions = p.list_remote_versions()?;
+                let versions = p.list_remote_versions_with_info()?;
                 Ok((p, versions))
             })
             .collect::<Result<Vec<_>>>()?
             .into_iter()
             .sorted_by_cached_key(|(p, _)| p.id().to_string())
             .collect::<Vec<_>>();
+        if self.json {
+            let versions: IndexMap<_, _> = versions
+                .into_iter()
+                .map(|(p, versions)| (p.id().to_string(), versions))
+                .collect();
+            miseprintln!("{}", serde_json::to_string_pretty(&versions)?);
+            return Ok(());
+        }
         for (plugin, versions) in versions {
             for v in versions {
-                miseprintln!("{}@{v}", plugin);
+                miseprintln!("{}@{}", plugin, v.version);
             }
         }
         Ok(())
@@ -116,5 +133,8 @@ static AFTER_LONG_HELP: &str = color_print::cstr!(
     $ <bold>mise ls-remote node 20</bold>
     20.0.0
     20.1.0
+
+    $ <bold>mise ls-remote ubi:BurntSushi/ripgrep --json</bold>
+    [{"version": "14.1.0", "created_at": "2024-01-06T21:26:52Z"}, ...]
 "#
 );
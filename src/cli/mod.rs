@@ -215,6 +215,10 @@ impl Cli {
                 .get_matches_from(args)
         });
         time!("run get_matches_from");
+        *crate::env::SUBCOMMANDS.write().unwrap() =
+            std::iter::successors(matches.subcommand(), |(_, m)| m.subcommand())
+                .map(|(name, _)| name.to_string())
+                .collect();
         Settings::add_cli_matches(&matches);
         time!("run add_cli_matches");
         logger::init();
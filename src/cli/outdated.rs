@@ -66,7 +66,11 @@ impl Outdated {
     }
 
     fn display(&self, outdated: Vec<OutdatedInfo>) -> Result<()> {
+        let has_age = outdated.iter().any(|o| o.current_created_at.is_some());
         let mut table = tabled::Table::new(outdated);
+        if !has_age {
+            table::disable_columns(&mut table, vec![3]);
+        }
         table::default_style(&mut table, self.no_header);
         miseprintln!("{table}");
         Ok(())
@@ -94,6 +98,10 @@ static AFTER_LONG_HELP: &str = color_print::cstr!(
     Plugin  Requested  Current  Latest
     node    20         20.0.0   20.1.0
 
+    $ <bold>mise outdated ripgrep</bold>
+    Plugin   Requested  Current  Age      Latest
+    ripgrep  latest     13.0.0   2 years  14.1.0
+
     $ <bold>mise outdated --json</bold>
     {"python": {"requested": "3.11", "current": "3.11.0", "latest": "3.11.1"}, ...}
 "#
@@ -1,10 +1,10 @@
-use std::collections::{BTreeMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::io::Write;
 use std::iter::once;
 use std::path::{Path, PathBuf};
 use std::process::Stdio;
 use std::sync::Mutex;
-use std::time::SystemTime;
+use std::time::{Duration, Instant, SystemTime};
 
 use super::args::ToolArg;
 use crate::cli::CLI;
@@ -12,10 +12,10 @@ use crate::cmd::CmdLineRunner;
 use crate::config::{CONFIG, SETTINGS};
 use crate::errors::Error;
 use crate::file::display_path;
-use crate::task::{Deps, GetMatchingExt, Task};
-use crate::toolset::{InstallOptions, ToolsetBuilder};
+use crate::task::{Deps, JunitReport, Task, TaskCache};
+use crate::toolset::{InstallOptions, Toolset, ToolsetBuilder};
 use crate::ui::{ctrlc, prompt, style, time};
-use crate::{dirs, env, exit, file, ui};
+use crate::{dirs, env, exit, file, hash, ui};
 use clap::ValueHint;
 use crossbeam_channel::{select, unbounded};
 use demand::{DemandOption, Select};
@@ -26,6 +26,7 @@ use glob::glob;
 use itertools::Itertools;
 #[cfg(unix)]
 use nix::sys::signal::SIGTERM;
+use serde_derive::Serialize;
 
 /// Run task(s)
 ///
@@ -93,6 +94,13 @@ pub struct Run {
     #[clap(long, short, verbatim_doc_comment, overrides_with = "prefix")]
     pub interleave: bool,
 
+    /// Change how task output is printed
+    /// `json` prints newline-delimited JSON events for task starts, output lines, exit statuses,
+    /// skipped tasks and the dependency graph
+    /// Configure with `task_output` config or `MISE_TASK_OUTPUT` env var
+    #[clap(long, short, verbatim_doc_comment, value_name = "OUTPUT")]
+    pub output: Option<TaskOutput>,
+
     /// Tool(s) to also add
     /// e.g.: node@20 python@3.10
     #[clap(short, long, value_name = "TOOL@VERSION")]
@@ -113,11 +121,29 @@ pub struct Run {
     #[clap(long, alias = "timing", verbatim_doc_comment)]
     pub timings: bool,
 
+    /// Keep running tasks that do not depend on a failed task and report every failure at the end
+    #[clap(long, verbatim_doc_comment)]
+    pub continue_on_error: bool,
+
+    /// Write a JUnit XML report with a testcase for each task to this file
+    /// Task output is captured for the report so tasks will not be connected to stdin
+    #[clap(long, value_hint = ValueHint::FilePath, value_name = "PATH", verbatim_doc_comment)]
+    pub junit: Option<PathBuf>,
+
     #[clap(skip)]
     pub is_linear: bool,
 
+    #[clap(skip)]
+    pub tool_versions: Vec<String>,
+
+    #[clap(skip)]
+    pub skipped_tasks: HashMap<String, String>,
+
     #[clap(skip)]
     pub failed_tasks: Mutex<Vec<(Task, i32)>>,
+
+    #[clap(skip)]
+    pub junit_report: Mutex<JunitReport>,
 }
 
 impl Run {
@@ -160,12 +186,7 @@ impl Run {
             })
             .flat_map(|args| args.split_first().map(|(t, a)| (t.clone(), a.to_vec())))
             .map(|(t, args)| {
-                let tasks = CONFIG
-                    .tasks_with_aliases()?
-                    .get_matching(&t)?
-                    .into_iter()
-                    .cloned()
-                    .collect_vec();
+                let tasks = CONFIG.tasks_matching(&t)?;
                 if tasks.is_empty() {
                     if t != "default" {
                         self.err_no_task(&t)?;
@@ -192,19 +213,33 @@ impl Run {
 
         ts.install_arg_versions(&CONFIG, &InstallOptions::new())?;
         ts.notify_if_versions_missing();
-        let mut env = ts.env_with_path(&CONFIG)?;
-        if let Some(root) = &CONFIG.project_root {
-            env.insert("MISE_PROJECT_ROOT".into(), root.display().to_string());
-            env.insert("root".into(), root.display().to_string());
-        }
+        self.tool_versions = ts
+            .list_current_versions()
+            .into_iter()
+            .map(|(_, tv)| tv.to_string())
+            .collect();
+        let env = self.toolset_env(&ts)?;
 
         let tasks = Deps::new(tasks)?;
+        self.skipped_tasks = tasks.skipped.clone();
         for task in tasks.all() {
-            self.validate_task(task)?;
+            if !self.skipped_tasks.contains_key(&task.name) {
+                self.validate_task(task)?;
+            }
         }
 
         let num_tasks = tasks.all().count();
         self.is_linear = tasks.is_linear();
+        if self.junit.is_some() {
+            // tasks are listed in the report even if they never run
+            let mut report = self.junit_report.lock().unwrap();
+            for task in tasks.all() {
+                report.start(task);
+            }
+        }
+        for (task, depends) in tasks.edges() {
+            self.emit(TaskEvent::Dependency { task, depends });
+        }
 
         let tasks = Mutex::new(tasks);
         let timer = std::time::Instant::now();
@@ -220,15 +255,39 @@ impl Run {
                 s.spawn(|_| {
                     let task = t;
                     let tx_err = tx_err;
+                    let mut failed = false;
                     if !self.is_stopping() {
                         trace!("running task: {task}");
                         if let Err(err) = self.run_task(&env, &task) {
                             let prefix = task.estyled_prefix();
-                            eprintln!("{prefix} {} {err}", style::ered("ERROR"),);
-                            let _ = tx_err.send((task.clone(), Error::get_exit_status(&err)));
+                            if task.allow_failure {
+                                eprintln!(
+                                    "{prefix} {} {err} (allowed to fail)",
+                                    style::eyellow("WARN")
+                                );
+                            } else {
+                                eprintln!("{prefix} {} {err}", style::ered("ERROR"),);
+                                self.add_failed_task(task.clone(), Error::get_exit_status(&err));
+                                let _ = tx_err.send(());
+                                failed = true;
+                            }
+                        }
+                    }
+                    let mut tasks = tasks.lock().unwrap();
+                    if failed && self.continue_on_error {
+                        for t in tasks.remove_dependents(&task) {
+                            eprintln!(
+                                "{} skipped because {} failed",
+                                t.estyled_prefix(),
+                                task.name
+                            );
+                            self.emit(TaskEvent::TaskSkipped {
+                                task: &t,
+                                reason: "dependency_failed",
+                            });
                         }
                     }
-                    tasks.lock().unwrap().remove(&task);
+                    tasks.remove(&task);
                 });
             };
             let rx = tasks.lock().unwrap().subscribe();
@@ -239,21 +298,26 @@ impl Run {
                             run(&task);
                         }
                     }
-                    recv(rx_err) -> task => { // a task errored
-                        let (task, status) = task.unwrap();
-                        self.add_failed_task(task, status);
-                        #[cfg(unix)]
-                        CmdLineRunner::kill_all(SIGTERM); // start killing other running tasks
-                        #[cfg(windows)]
-                        CmdLineRunner::kill_all();
+                    recv(rx_err) -> _ => { // a task errored
+                        if !self.continue_on_error {
+                            #[cfg(unix)]
+                            CmdLineRunner::kill_all(SIGTERM); // start killing other running tasks
+                            #[cfg(windows)]
+                            CmdLineRunner::kill_all();
+                        }
                     }
                 }
             }
         });
 
-        if let Some((task, status)) = self.failed_tasks.lock().unwrap().first() {
+        self.write_junit_report()?;
+
+        let failed_tasks = self.failed_tasks.lock().unwrap();
+        for (task, _) in failed_tasks.iter() {
             let prefix = task.estyled_prefix();
             eprintln!("{prefix} {} task failed", style::ered("ERROR"));
+        }
+        if let Some((_, status)) = failed_tasks.first() {
             exit(*status);
         }
 
@@ -269,11 +333,22 @@ impl Run {
 
     fn run_task(&self, env: &BTreeMap<String, String>, task: &Task) -> Result<()> {
         let prefix = task.estyled_prefix();
-        if !self.force && self.sources_are_fresh(task) {
-            eprintln!("{prefix} sources up-to-date, skipping");
+
+        if let Some(reason) = self.skipped_tasks.get(&task.name) {
+            eprintln!("{prefix} {reason}, skipping");
+            self.emit(TaskEvent::TaskSkipped {
+                task,
+                reason: "condition",
+            });
             return Ok(());
         }
 
+        let tools_env = match task.tools.is_empty() {
+            true => None,
+            false => Some(self.task_tools_env(task)?),
+        };
+        let env = tools_env.as_ref().unwrap_or(env);
+
         let string_env = task.env.iter().filter_map(|(k, v)| match &v.0 {
             Either::Left(v) => Some((k, v)),
             _ => None,
@@ -291,15 +366,37 @@ impl Run {
             .map(|(k, v)| (k.clone(), v.clone()))
             .collect();
 
+        if !self.force && self.sources_are_fresh(task, &env) {
+            eprintln!("{prefix} sources up-to-date, skipping");
+            self.emit(TaskEvent::TaskSkipped {
+                task,
+                reason: "sources_fresh",
+            });
+            return Ok(());
+        }
+        if !self.force && self.restore_from_cache(task, &env) {
+            eprintln!("{prefix} outputs restored from cache, skipping");
+            self.emit(TaskEvent::TaskSkipped {
+                task,
+                reason: "cached",
+            });
+            self.save_checksum(task, &env)?;
+            return Ok(());
+        }
+
         let timer = std::time::Instant::now();
 
-        if let Some(file) = &task.file {
-            self.exec_file(file, task, &env, &prefix)?;
-        } else {
-            for (script, args) in task.render_run_scripts_with_args(self.cd.clone(), &task.args)? {
-                self.exec_script(&script, &args, task, &env, &prefix)?;
-            }
-        }
+        self.emit(TaskEvent::TaskStarted { task });
+        let result = self.exec_task_with_retries(task, &env, &prefix);
+        self.emit(TaskEvent::TaskFinished {
+            task,
+            status: match &result {
+                Ok(()) => 0,
+                Err(err) => Error::get_exit_status(err).unwrap_or(1),
+            },
+            duration: timer.elapsed(),
+        });
+        result?;
 
         if self.timings {
             eprintln!(
@@ -308,11 +405,77 @@ impl Run {
             );
         }
 
-        self.save_checksum(task)?;
+        self.save_checksum(task, &env)?;
+        self.save_to_cache(task, &env);
 
         Ok(())
     }
 
+    fn toolset_env(&self, ts: &Toolset) -> Result<BTreeMap<String, String>> {
+        let mut env = ts.env_with_path(&CONFIG)?;
+        if let Some(root) = &CONFIG.project_root {
+            env.insert("MISE_PROJECT_ROOT".into(), root.display().to_string());
+            env.insert("root".into(), root.display().to_string());
+        }
+        Ok(env)
+    }
+
+    /// env of a task with its `tools` installed and layered over the project's toolset
+    fn task_tools_env(&self, task: &Task) -> Result<BTreeMap<String, String>> {
+        let mut args = self.tool.clone();
+        for (tool, versions) in &task.tools {
+            for version in versions.split_whitespace() {
+                args.push(format!("{tool}@{version}").parse()?);
+            }
+        }
+        let mut ts = ToolsetBuilder::new().with_args(&args).build(&CONFIG)?;
+        ts.install_arg_versions(&CONFIG, &InstallOptions::new())?;
+        self.toolset_env(&ts)
+    }
+
+    fn exec_task_with_retries(
+        &self,
+        task: &Task,
+        env: &BTreeMap<String, String>,
+        prefix: &str,
+    ) -> Result<()> {
+        let mut attempt = 0;
+        loop {
+            let deadline = task.timeout()?.map(|t| Instant::now() + t);
+            match self.exec_task(task, env, prefix, deadline) {
+                Err(err) if attempt < task.retry && !self.is_stopping() => {
+                    attempt += 1;
+                    let backoff = task.retry_backoff(attempt)?;
+                    eprintln!(
+                        "{prefix} {} {err}, retrying in {} ({attempt}/{})",
+                        style::eyellow("WARN"),
+                        humantime::format_duration(backoff),
+                        task.retry
+                    );
+                    std::thread::sleep(backoff);
+                }
+                result => return result,
+            }
+        }
+    }
+
+    fn exec_task(
+        &self,
+        task: &Task,
+        env: &BTreeMap<String, String>,
+        prefix: &str,
+        deadline: Option<Instant>,
+    ) -> Result<()> {
+        if let Some(file) = &task.file {
+            self.exec_file(file, task, env, prefix, deadline)?;
+        } else {
+            for (script, args) in task.render_run_scripts_with_args(self.cd.clone(), &task.args)? {
+                self.exec_script(&script, &args, task, env, prefix, deadline)?;
+            }
+        }
+        Ok(())
+    }
+
     fn exec_script(
         &self,
         script: &str,
@@ -320,6 +483,7 @@ impl Run {
         task: &Task,
         env: &BTreeMap<String, String>,
         prefix: &str,
+        deadline: Option<Instant>,
     ) -> Result<()> {
         let script = script.trim_start();
         let cmd = trunc(&style::ebold(format!("$ {script}")).bright().to_string());
@@ -334,7 +498,7 @@ impl Run {
             drop(tmp);
             file::make_executable(&file)?;
             let filename = file.display().to_string();
-            self.exec(&filename, args, task, env, prefix)
+            self.exec(&filename, args, task, env, prefix, deadline)
         } else {
             let shell = self.get_shell(task);
             trace!("using shell: {} {}", shell.0, shell.1);
@@ -342,13 +506,13 @@ impl Run {
             {
                 let script = format!("{} {}", script, args.join(" "));
                 let args = vec![shell.1, script];
-                self.exec(shell.0.as_str(), &args, task, env, prefix)
+                self.exec(shell.0.as_str(), &args, task, env, prefix, deadline)
             }
             #[cfg(unix)]
             {
                 let script = format!("{} {}", script, shell_words::join(args));
                 let args = vec![shell.1, script];
-                self.exec(shell.0.as_str(), &args, task, env, prefix)
+                self.exec(shell.0.as_str(), &args, task, env, prefix, deadline)
             }
         }
     }
@@ -380,6 +544,7 @@ impl Run {
         task: &Task,
         env: &BTreeMap<String, String>,
         prefix: &str,
+        deadline: Option<Instant>,
     ) -> Result<()> {
         let mut env = env.clone();
         let command = file.to_string_lossy().to_string();
@@ -397,7 +562,7 @@ impl Run {
         let cmd = trunc(&style::ebold(format!("$ {cmd}")).bright().to_string());
         eprintln!("{prefix} {cmd}");
 
-        self.exec(&command, &args, task, &env, prefix)
+        self.exec(&command, &args, task, &env, prefix, deadline)
     }
 
     fn exec(
@@ -407,22 +572,47 @@ impl Run {
         task: &Task,
         env: &BTreeMap<String, String>,
         prefix: &str,
+        deadline: Option<Instant>,
     ) -> Result<()> {
         let program = program.to_executable();
         let mut cmd = CmdLineRunner::new(program.clone()).args(args).envs(env);
         cmd.with_pass_signals();
-        match &self.output(task)? {
-            TaskOutput::Prefix => cmd = cmd.prefix(format!("{prefix} ")),
-            TaskOutput::Interleave => {
+        match self.output(Some(task))? {
+            TaskOutput::Prefix if self.junit.is_none() => cmd = cmd.prefix(format!("{prefix} ")),
+            TaskOutput::Interleave if self.junit.is_none() => {
                 cmd = cmd
                     .stdin(Stdio::inherit())
                     .stdout(Stdio::inherit())
                     .stderr(Stdio::inherit())
             }
+            output => {
+                // capture output as events for --output=json and --junit
+                let line_prefix = match output {
+                    TaskOutput::Prefix => format!("{prefix} "),
+                    _ => String::new(),
+                };
+                let line_prefix_err = line_prefix.clone();
+                cmd = cmd
+                    .with_on_stdout(move |line| {
+                        self.emit(TaskEvent::Stdout { task, line: &line });
+                        if output != TaskOutput::Json {
+                            println!("{line_prefix}{line}");
+                        }
+                    })
+                    .with_on_stderr(move |line| {
+                        self.emit(TaskEvent::Stderr { task, line: &line });
+                        if output != TaskOutput::Json {
+                            eprintln!("{line_prefix_err}{line}");
+                        }
+                    })
+            }
         }
         if self.raw(task) {
             cmd.with_raw();
         }
+        if let Some(deadline) = deadline {
+            cmd = cmd.with_timeout(deadline.saturating_duration_since(Instant::now()));
+        }
         if let Some(cd) = &self.cd.as_ref().or(task.dir.as_ref()) {
             cmd = cmd.current_dir(cd);
         }
@@ -434,20 +624,51 @@ impl Run {
         Ok(())
     }
 
-    fn output(&self, task: &Task) -> Result<TaskOutput> {
-        if self.prefix {
+    fn output(&self, task: Option<&Task>) -> Result<TaskOutput> {
+        if let Some(output) = self.output {
+            Ok(output)
+        } else if self.prefix {
             Ok(TaskOutput::Prefix)
         } else if self.interleave {
             Ok(TaskOutput::Interleave)
         } else if let Some(output) = &SETTINGS.task_output {
             Ok(output.parse()?)
-        } else if self.raw(task) || self.jobs() == 1 || self.is_linear {
+        } else if task.map_or(self.raw || SETTINGS.raw, |t| self.raw(t))
+            || self.jobs() == 1
+            || self.is_linear
+        {
             Ok(TaskOutput::Interleave)
         } else {
             Ok(TaskOutput::Prefix)
         }
     }
 
+    /// prints an event to stdout as a line of JSON if `--output=json` and adds it to the JUnit report
+    fn emit(&self, event: TaskEvent) {
+        if self.junit.is_some() {
+            let mut report = self.junit_report.lock().unwrap();
+            match &event {
+                TaskEvent::Dependency { .. } => {}
+                TaskEvent::TaskStarted { task } => report.start(task),
+                TaskEvent::TaskSkipped { task, reason } => report.skip(task, reason),
+                TaskEvent::Stdout { task, line } => report.stdout(task, line),
+                TaskEvent::Stderr { task, line } => report.stderr(task, line),
+                TaskEvent::TaskFinished {
+                    task,
+                    status,
+                    duration,
+                } => report.finish(task, *status, *duration),
+            }
+        }
+        if !matches!(self.output(None), Ok(TaskOutput::Json)) {
+            return;
+        }
+        match serde_json::to_string(&event) {
+            Ok(json) => println!("{json}"),
+            Err(err) => warn!("failed to serialize task event: {err}"),
+        }
+    }
+
     fn raw(&self, task: &Task) -> bool {
         self.raw || task.raw || SETTINGS.raw
     }
@@ -470,8 +691,10 @@ impl Run {
         let mut s = Select::new("Tasks")
             .description("Select a tasks to run")
             .filterable(true);
-        for name in tasks.keys() {
-            s = s.option(DemandOption::new(name));
+        for (name, task) in tasks.iter() {
+            if task.skip_reason().unwrap_or_default().is_none() {
+                s = s.option(DemandOption::new(name));
+            }
         }
         ctrlc::show_cursor_after_ctrl_c();
         let name = s.run()?;
@@ -482,6 +705,8 @@ impl Run {
     }
 
     fn validate_task(&self, task: &Task) -> Result<()> {
+        task.timeout()?;
+        task.retry_backoff(0)?;
         if let Some(path) = &task.file {
             if !file::is_executable(path) {
                 let dp = display_path(path);
@@ -496,11 +721,29 @@ impl Run {
         Ok(())
     }
 
-    fn sources_are_fresh(&self, task: &Task) -> bool {
+    fn sources_are_fresh(&self, task: &Task, env: &BTreeMap<String, String>) -> bool {
         if task.sources.is_empty() && task.outputs.is_empty() {
             return false;
         }
         let run = || -> Result<bool> {
+            if SETTINGS.task_checksums {
+                if task.sources.is_empty() {
+                    return Ok(false);
+                }
+                let path = self.checksum_path(task);
+                if !path.exists() {
+                    return Ok(false);
+                }
+                if !task.outputs.is_empty()
+                    && get_source_files(&self.cwd(task), &task.outputs).is_empty()
+                {
+                    return Ok(false);
+                }
+                let prev = file::read_to_string(&path)?;
+                let current = self.task_checksum(task, env)?;
+                trace!("checksum: {current}, previous: {}", prev.trim());
+                return Ok(prev.trim() == current);
+            }
             let sources = self.get_last_modified(&self.cwd(task), &task.sources)?;
             let outputs = self.get_last_modified(&self.cwd(task), &task.outputs)?;
             trace!("sources: {sources:?}, outputs: {outputs:?}");
@@ -515,6 +758,20 @@ impl Run {
         })
     }
 
+    fn write_junit_report(&self) -> Result<()> {
+        if let Some(path) = &self.junit {
+            let mut report = self.junit_report.lock().unwrap();
+            for (task, status) in self.failed_tasks.lock().unwrap().iter() {
+                report.fail(task, *status);
+            }
+            if let Some(parent) = path.parent() {
+                file::create_dir_all(parent)?;
+            }
+            file::write(path, report.to_xml())?;
+        }
+        Ok(())
+    }
+
     fn add_failed_task(&self, task: Task, status: Option<i32>) {
         self.failed_tasks
             .lock()
@@ -523,7 +780,7 @@ impl Run {
     }
 
     fn is_stopping(&self) -> bool {
-        !self.failed_tasks.lock().unwrap().is_empty()
+        !self.continue_on_error && !self.failed_tasks.lock().unwrap().is_empty()
     }
 
     fn get_last_modified(
@@ -558,14 +815,124 @@ impl Run {
             .unwrap_or_else(|| env::current_dir().unwrap().clone())
     }
 
-    fn save_checksum(&self, task: &Task) -> Result<()> {
-        if task.sources.is_empty() {
+    fn checksum_path(&self, task: &Task) -> PathBuf {
+        let id = hash::hash_to_str(&(&task.config_source, &task.name, self.cwd(task)));
+        dirs::STATE.join("task-checksums").join(id)
+    }
+
+    /// sha256 of the files matched by `sources` along with the rendered scripts and env of a task
+    fn sources_checksum(&self, task: &Task, env: &BTreeMap<String, String>) -> Result<String> {
+        let root = self.cwd(task);
+        // paths in scripts and env (MISE_PROJECT_ROOT, {{config_root}}, ...) are made relative to
+        // the task's root and home so checkouts in different locations share the task cache
+        let (root_str, home) = (root.to_string_lossy(), dirs::HOME.to_string_lossy());
+        let relative = |s: &str| {
+            s.replace(root_str.as_ref(), "{{root}}")
+                .replace(home.as_ref(), "~")
+        };
+        let mut lines = vec![];
+        for path in get_source_files(&root, &task.sources) {
+            let hash = hash::file_hash_sha256(&path)?;
+            let path = path.strip_prefix(&root).unwrap_or(&path);
+            lines.push(format!("source {hash} {}", path.display()));
+        }
+        if let Some(file) = &task.file {
+            lines.push(format!("file {}", hash::file_hash_sha256(file)?));
+            lines.push(format!("args {}", relative(&task.args.join(" "))));
+        } else {
+            for (script, args) in task.render_run_scripts_with_args(self.cd.clone(), &task.args)? {
+                lines.push(relative(&format!("run {script} {}", args.join(" "))));
+            }
+        }
+        for (k, v) in env {
+            lines.push(format!("env {k}={}", relative(v)));
+        }
+        Ok(hash::hash_sha256_to_str(&lines.join("\n")))
+    }
+
+    /// sha256 of the inputs of a task along with the outputs it created so that changing or
+    /// deleting an output makes the task run again
+    fn task_checksum(&self, task: &Task, env: &BTreeMap<String, String>) -> Result<String> {
+        let root = self.cwd(task);
+        let mut lines = vec![format!("inputs {}", self.sources_checksum(task, env)?)];
+        for path in get_source_files(&root, &task.outputs) {
+            let hash = hash::file_hash_sha256(&path)?;
+            let path = path.strip_prefix(&root).unwrap_or(&path);
+            lines.push(format!("output {hash} {}", path.display()));
+        }
+        Ok(hash::hash_sha256_to_str(&lines.join("\n")))
+    }
+
+    fn save_checksum(&self, task: &Task, env: &BTreeMap<String, String>) -> Result<()> {
+        if task.sources.is_empty() || !SETTINGS.task_checksums || self.dry_run {
             return Ok(());
         }
-        // TODO
+        let checksum = self.task_checksum(task, env)?;
+        let path = self.checksum_path(task);
+        file::create_dir_all(path.parent().unwrap())?;
+        file::write(&path, checksum)?;
         Ok(())
     }
 
+    fn uses_cache(&self, task: &Task) -> bool {
+        SETTINGS.task_cache && !task.sources.is_empty() && !task.outputs.is_empty() && !self.dry_run
+    }
+
+    /// key of a task in the task cache, derived from its sources, definition, tools and env
+    fn cache_key(&self, task: &Task, env: &BTreeMap<String, String>) -> Result<String> {
+        // PATH differs between machines sharing a cache, the tools on it are keyed separately
+        let env = env
+            .iter()
+            .filter(|(k, _)| *k != "PATH")
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        let lines = [
+            format!("task {}", task.name),
+            format!("inputs {}", self.sources_checksum(task, &env)?),
+            format!("outputs {}", task.outputs.join(" ")),
+            format!("tools {}", self.tool_versions.join(" ")),
+            format!(
+                "task tools {}",
+                task.tools.iter().map(|(k, v)| format!("{k}@{v}")).join(" ")
+            ),
+        ];
+        Ok(hash::hash_sha256_to_str(&lines.join("\n")))
+    }
+
+    fn restore_from_cache(&self, task: &Task, env: &BTreeMap<String, String>) -> bool {
+        if !self.uses_cache(task) {
+            return false;
+        }
+        let run = || -> Result<bool> {
+            let key = self.cache_key(task, env)?;
+            trace!("task cache key: {key}");
+            TaskCache::new(&key).restore(&self.cwd(task))
+        };
+        run().unwrap_or_else(|err| {
+            warn!("failed to restore {task} from cache: {err:#}");
+            false
+        })
+    }
+
+    fn save_to_cache(&self, task: &Task, env: &BTreeMap<String, String>) {
+        if !self.uses_cache(task) {
+            return;
+        }
+        let run = || -> Result<()> {
+            let root = self.cwd(task);
+            let outputs = get_source_files(&root, &task.outputs);
+            if outputs.is_empty() {
+                warn!("{task} did not create any of its outputs, not caching");
+                return Ok(());
+            }
+            let key = self.cache_key(task, env)?;
+            TaskCache::new(&key).save(&task.name, &root, &outputs)
+        };
+        if let Err(err) = run() {
+            warn!("failed to save {task} to cache: {err:#}");
+        }
+    }
+
     fn err_no_task(&self, name: &str) -> Result<()> {
         if let Some(cwd) = &*dirs::CWD {
             let includes = CONFIG.task_includes_for_dir(cwd);
@@ -604,20 +971,15 @@ fn last_modified_path(
     root: impl AsRef<std::ffi::OsStr>,
     paths: &[&String],
 ) -> Result<Option<SystemTime>> {
-    let files = paths
-        .iter()
-        .map(|p| {
-            let base = Path::new(p);
-
-            if base.is_relative() {
-                base.to_path_buf()
-            } else {
-                Path::new(&root).join(base)
-            }
-        })
-        .filter(|p| p.is_file());
+    last_modified_file(path_matches(root, paths))
+}
 
-    last_modified_file(files)
+fn path_matches(root: impl AsRef<std::ffi::OsStr>, paths: &[&String]) -> Vec<PathBuf> {
+    paths
+        .iter()
+        .map(|p| Path::new(&root).join(p))
+        .filter(|p| p.is_file())
+        .collect()
 }
 
 fn last_modified_glob_match(
@@ -627,7 +989,11 @@ fn last_modified_glob_match(
     if patterns.is_empty() {
         return Ok(None);
     }
-    let files = patterns
+    last_modified_file(glob_matches(root, patterns))
+}
+
+fn glob_matches(root: impl AsRef<Path>, patterns: &[&String]) -> Vec<PathBuf> {
+    patterns
         .iter()
         .flat_map(|pattern| {
             glob(
@@ -644,9 +1010,17 @@ fn last_modified_glob_match(
                 .expect("Metadata call failed")
                 .file_type()
                 .is_file()
-        });
+        })
+        .collect()
+}
 
-    last_modified_file(files)
+/// all files matched by a task's `sources`, sorted so they can be checksummed
+pub(crate) fn get_source_files(root: &Path, patterns_or_paths: &[String]) -> Vec<PathBuf> {
+    let (patterns, paths): (Vec<&String>, Vec<&String>) =
+        patterns_or_paths.iter().partition(|p| is_glob_pattern(p));
+    let mut files = glob_matches(root, &patterns);
+    files.extend(path_matches(root, &paths));
+    files.into_iter().unique().sorted().collect()
 }
 
 fn last_modified_file(files: impl IntoIterator<Item = PathBuf>) -> Result<Option<SystemTime>> {
@@ -684,11 +1058,58 @@ static AFTER_LONG_HELP: &str = color_print::cstr!(
 "#
 );
 
-#[derive(Debug, PartialEq, strum::EnumString)]
+#[derive(Debug, Clone, Copy, PartialEq, strum::EnumString, strum::Display, clap::ValueEnum)]
 #[strum(serialize_all = "snake_case")]
-enum TaskOutput {
+pub enum TaskOutput {
     Prefix,
     Interleave,
+    Json,
+}
+
+/// events printed as newline-delimited JSON with `--output=json`
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum TaskEvent<'a> {
+    Dependency {
+        #[serde(serialize_with = "serialize_task_name")]
+        task: &'a Task,
+        #[serde(serialize_with = "serialize_task_name")]
+        depends: &'a Task,
+    },
+    TaskStarted {
+        #[serde(serialize_with = "serialize_task_name")]
+        task: &'a Task,
+    },
+    TaskSkipped {
+        #[serde(serialize_with = "serialize_task_name")]
+        task: &'a Task,
+        reason: &'a str,
+    },
+    Stdout {
+        #[serde(serialize_with = "serialize_task_name")]
+        task: &'a Task,
+        line: &'a str,
+    },
+    Stderr {
+        #[serde(serialize_with = "serialize_task_name")]
+        task: &'a Task,
+        line: &'a str,
+    },
+    TaskFinished {
+        #[serde(serialize_with = "serialize_task_name")]
+        task: &'a Task,
+        status: i32,
+        #[serde(rename = "duration_ms", serialize_with = "serialize_duration_ms")]
+        duration: Duration,
+    },
+}
+
+fn serialize_task_name<S: serde::Serializer>(task: &&Task, s: S) -> Result<S::Ok, S::Error> {
+    s.serialize_str(&task.name)
+}
+
+fn serialize_duration_ms<S: serde::Serializer>(d: &Duration, s: S) -> Result<S::Ok, S::Error> {
+    s.serialize_u128(d.as_millis())
 }
 
 fn trunc(msg: &str) -> String {
@@ -698,11 +1119,18 @@ fn trunc(msg: &str) -> String {
 
 #[cfg(test)]
 mod tests {
+    use std::collections::BTreeMap;
+
+    use clap::{Args, FromArgMatches};
     use insta::assert_snapshot;
+    use pretty_assertions::{assert_eq, assert_ne};
 
     use crate::file;
+    use crate::task::Task;
     use crate::test::reset;
 
+    use super::Run;
+
     #[test]
     fn test_task_run() {
         reset();
@@ -736,6 +1164,73 @@ mod tests {
         "###);
     }
 
+    #[test]
+    fn test_task_checksum() {
+        reset();
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let cmd = Run::augment_args(clap::Command::new("run"));
+        let matches = cmd
+            .try_get_matches_from(["run", "--cd", root.to_str().unwrap()])
+            .unwrap();
+        let run = Run::from_arg_matches(&matches).unwrap();
+        let task = Task {
+            name: "build".into(),
+            run: vec!["cat src.txt > out/bin.txt".into()],
+            sources: vec!["src.txt".into()],
+            outputs: vec!["out/*.txt".into()],
+            ..Default::default()
+        };
+        let env = BTreeMap::new();
+        let checksum = || run.task_checksum(&task, &env).unwrap();
+        file::write(root.join("src.txt"), "a").unwrap();
+        file::create_dir_all(root.join("out")).unwrap();
+        file::write(root.join("out/bin.txt"), "a").unwrap();
+        let saved = checksum();
+        assert_eq!(checksum(), saved);
+
+        file::write(root.join("src.txt"), "b").unwrap();
+        assert_ne!(checksum(), saved);
+        file::write(root.join("src.txt"), "a").unwrap();
+        assert_eq!(checksum(), saved);
+
+        file::write(root.join("out/bin.txt"), "b").unwrap();
+        assert_ne!(checksum(), saved);
+        file::write(root.join("out/bin.txt"), "a").unwrap();
+        assert_eq!(checksum(), saved);
+
+        file::remove_file(root.join("out/bin.txt")).unwrap();
+        assert_ne!(checksum(), saved);
+    }
+
+    #[test]
+    fn test_cache_key() {
+        reset();
+        let cache_key = |root: &std::path::Path| {
+            let cmd = Run::augment_args(clap::Command::new("run"));
+            let matches = cmd
+                .try_get_matches_from(["run", "--cd", root.to_str().unwrap()])
+                .unwrap();
+            let run = Run::from_arg_matches(&matches).unwrap();
+            let root = root.to_string_lossy();
+            let task = Task {
+                name: "build".into(),
+                run: vec![format!("cp {root}/src.txt {root}/out/bin.txt")],
+                sources: vec!["src.txt".into()],
+                outputs: vec!["out/bin.txt".into()],
+                ..Default::default()
+            };
+            let env = BTreeMap::from([("MISE_PROJECT_ROOT".into(), root.to_string())]);
+            run.cache_key(&task, &env).unwrap()
+        };
+        let (a, b) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        file::write(a.path().join("src.txt"), "a").unwrap();
+        file::write(b.path().join("src.txt"), "a").unwrap();
+        assert_eq!(cache_key(a.path()), cache_key(b.path()));
+        file::write(b.path().join("src.txt"), "b").unwrap();
+        assert_ne!(cache_key(a.path()), cache_key(b.path()));
+    }
+
     #[test]
     fn test_task_custom_shell_invalid() {
         reset();
@@ -44,12 +44,13 @@ impl Set {
     pub fn run(self) -> Result<()> {
         let config = Config::try_get()?;
         if self.remove.is_none() && self.env_vars.is_none() {
+            let env_results = config.env_results()?;
             let rows = config
                 .env_with_sources()?
                 .iter()
                 .map(|(key, (value, source))| Row {
                     key: key.clone(),
-                    value: value.clone(),
+                    value: env_results.redact(key, value).to_string(),
                     source: display_path(source),
                 })
                 .collect::<Vec<_>>();
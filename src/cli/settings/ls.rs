@@ -73,6 +73,7 @@ mod tests {
         experimental = true
         fetch_remote_versions_cache = "1h"
         fetch_remote_versions_timeout = "10s"
+        github_api_url = "https://api.github.com"
         go_default_packages_file = "~/.default-go-packages"
         go_download_mirror = "https://dl.google.com/go"
         go_repo = "https://github.com/golang/go"
@@ -84,6 +85,7 @@ mod tests {
         legacy_version_file = true
         legacy_version_file_disable_tools = []
         libgit2 = true
+        locked = false
         lockfile = false
         not_found_auto_install = true
         paranoid = false
@@ -91,6 +93,8 @@ mod tests {
         plugin_autoupdate_last_check_duration = "20m"
         quiet = false
         raw = false
+        task_cache = false
+        task_checksums = false
         trusted_config_paths = []
         use_versions_host = true
         verbose = true
@@ -143,6 +147,7 @@ mod tests {
         experimental
         fetch_remote_versions_cache
         fetch_remote_versions_timeout
+        github_api_url
         go_default_packages_file
         go_download_mirror
         go_repo
@@ -154,6 +159,7 @@ mod tests {
         legacy_version_file
         legacy_version_file_disable_tools
         libgit2
+        locked
         lockfile
         node
         not_found_auto_install
@@ -178,6 +184,8 @@ mod tests {
         status.missing_tools
         status.show_env
         status.show_tools
+        task_cache
+        task_checksums
         trusted_config_paths
         use_versions_host
         verbose
@@ -64,6 +64,7 @@ mod tests {
         experimental = true
         fetch_remote_versions_cache = "1h"
         fetch_remote_versions_timeout = "10s"
+        github_api_url = "https://api.github.com"
         go_default_packages_file = "~/.default-go-packages"
         go_download_mirror = "https://dl.google.com/go"
         go_repo = "https://github.com/golang/go"
@@ -75,6 +76,7 @@ mod tests {
         legacy_version_file = true
         legacy_version_file_disable_tools = []
         libgit2 = true
+        locked = false
         lockfile = false
         not_found_auto_install = true
         paranoid = false
@@ -82,6 +84,8 @@ mod tests {
         plugin_autoupdate_last_check_duration = "20m"
         quiet = false
         raw = false
+        task_cache = false
+        task_checksums = false
         trusted_config_paths = []
         use_versions_host = true
         verbose = true
@@ -1,4 +1,5 @@
 use eyre::{bail, Result};
+use itertools::Itertools;
 use serde_json::json;
 
 use crate::config::CONFIG;
@@ -52,6 +53,9 @@ impl TasksInfo {
         if task.raw {
             properties.push("raw");
         }
+        if task.allow_failure {
+            properties.push("allow_failure");
+        }
         if !properties.is_empty() {
             info::inline_section("Properties", properties.join(", "))?;
         }
@@ -67,6 +71,24 @@ impl TasksInfo {
         if !task.outputs.is_empty() {
             info::inline_section("Outputs", task.outputs.join(", "))?;
         }
+        if let Some(condition) = task.condition_str() {
+            info::inline_section("Condition", condition)?;
+        }
+        if let Some(timeout) = &task.timeout {
+            info::inline_section("Timeout", timeout)?;
+        }
+        if task.retry > 0 {
+            let backoff = task.retry_backoff.as_deref().unwrap_or("1s");
+            info::inline_section("Retry", format!("{} (backoff: {backoff})", task.retry))?;
+        }
+        if !task.tools.is_empty() {
+            let tools = task
+                .tools
+                .iter()
+                .map(|(k, v)| format!("{k}@{v}"))
+                .join(", ");
+            info::inline_section("Tools", tools)?;
+        }
         if let Some(file) = &task.file {
             info::inline_section("File", display_path(file))?;
         }
@@ -93,10 +115,18 @@ impl TasksInfo {
             "depends": task.depends.join(", "),
             "env": task.env,
             "dir": task.dir,
+            "tools": task.tools,
             "hide": task.hide,
             "raw": task.raw,
             "sources": task.sources,
             "outputs": task.outputs,
+            "timeout": task.timeout,
+            "retry": task.retry,
+            "retry_backoff": task.retry_backoff,
+            "allow_failure": task.allow_failure,
+            "if": task.condition,
+            "os": task.os,
+            "arch": task.arch,
             "run": task.run,
             "file": task.file,
             "usage_spec": spec,
@@ -124,10 +154,18 @@ static AFTER_LONG_HELP: &str = color_print::cstr!(
       "depends": [],
       "env": {},
       "dir": null,
+      "tools": {},
       "hide": false,
       "raw": false,
       "sources": [],
       "outputs": [],
+      "timeout": null,
+      "retry": 0,
+      "retry_backoff": null,
+      "allow_failure": false,
+      "if": null,
+      "os": [],
+      "arch": [],
       "run": [
         "echo \"testing!\""
       ],
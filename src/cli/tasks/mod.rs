@@ -7,6 +7,7 @@ mod deps;
 mod edit;
 mod info;
 mod ls;
+mod update;
 
 /// Manage tasks
 #[derive(Debug, clap::Args)]
@@ -25,7 +26,8 @@ enum Commands {
     Edit(edit::TasksEdit),
     Info(info::TasksInfo),
     Ls(ls::TasksLs),
-    Run(run::Run),
+    Run(Box<run::Run>),
+    Update(update::TasksUpdate),
 }
 
 impl Commands {
@@ -35,7 +37,8 @@ impl Commands {
             Self::Edit(cmd) => cmd.run(),
             Self::Info(cmd) => cmd.run(),
             Self::Ls(cmd) => cmd.run(),
-            Self::Run(cmd) => cmd.run(),
+            Self::Run(cmd) => (*cmd).run(),
+            Self::Update(cmd) => cmd.run(),
         }
     }
 }
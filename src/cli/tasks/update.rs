@@ -0,0 +1,46 @@
+use eyre::Result;
+use itertools::Itertools;
+
+use crate::config::{config_file, Config};
+use crate::task::TaskInclude;
+
+/// Update remote task includes
+///
+/// Fetches the latest version of task includes from git repositories and https archives
+/// listed in `task_config.includes`. Remote includes are otherwise only fetched the first
+/// time they are used.
+#[derive(Debug, clap::Args)]
+#[clap(verbatim_doc_comment, after_long_help = AFTER_LONG_HELP)]
+pub struct TasksUpdate {}
+
+impl TasksUpdate {
+    pub fn run(self) -> Result<()> {
+        let config = Config::try_get()?;
+        let includes = config
+            .config_files
+            .values()
+            .flat_map(|cf| {
+                cf.task_config()
+                    .includes
+                    .iter()
+                    .flatten()
+                    .filter_map(|p| TaskInclude::parse(&p.to_string_lossy()))
+                    .map(|include| (cf.get_path().to_path_buf(), include))
+                    .collect_vec()
+            })
+            .unique_by(|(_, include)| include.clone())
+            .collect_vec();
+        for (path, include) in includes {
+            config_file::trust_check(&path)?;
+            include.fetch(true)?;
+        }
+        Ok(())
+    }
+}
+
+static AFTER_LONG_HELP: &str = color_print::cstr!(
+    r#"<bold><underline>Examples:</underline></bold>
+
+    $ <bold>mise tasks update</bold>
+"#
+);
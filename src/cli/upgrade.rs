@@ -137,6 +137,7 @@ impl Upgrade {
             }
             return Ok(());
         }
+        lockfile::ensure_unlocked("mise upgrade")?;
         let opts = InstallOptions {
             force: false,
             jobs: self.jobs,
@@ -85,6 +85,7 @@ pub struct Use {
 
 impl Use {
     pub fn run(self) -> Result<()> {
+        lockfile::ensure_unlocked("mise use")?;
         let config = Config::try_get()?;
         let mut ts = ToolsetBuilder::new().build(&config)?;
         let mpr = MultiProgressReport::get();
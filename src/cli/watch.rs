@@ -1,17 +1,32 @@
-use crate::exit;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command};
+use std::sync::mpsc;
+use std::time::Duration;
 
-use console::style;
+use clap::ValueHint;
 use eyre::{eyre, Result};
+use glob::{MatchOptions, Pattern};
+use itertools::Itertools;
+use notify::event::{CreateKind, ModifyKind, RemoveKind};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode};
 
-use crate::cli::args::BackendArg;
-use crate::cmd;
+use crate::cli::args::ToolArg;
+use crate::cli::run::{get_source_files, TaskOutput};
 use crate::config::Config;
-use crate::toolset::ToolsetBuilder;
+use crate::env;
+use crate::task::Task;
+use crate::ui::style;
 
 /// Run task(s) and watch for changes to rerun it
 ///
-/// This command uses the `watchexec` tool to watch for changes to files and rerun the specified task(s).
-/// It must be installed for this command to work, but you can install it with `mise use -g watchexec@latest`.
+/// This command watches the files matched by the `sources` of the task(s) and reruns them when
+/// any of those files change. Changes are debounced so a burst of writes only triggers one run.
+/// If the tasks are still running when a change is detected, `--restart` will cancel them and
+/// start them again, otherwise mise waits for them to finish before rerunning.
+///
+/// Files are watched by mise itself, watchexec is no longer needed. Extra arguments are passed to
+/// the last task instead of to watchexec and `--tool` has no short flag since `-t` is `--task`.
 #[derive(Debug, clap::Args)]
 #[clap(visible_alias = "w", verbatim_doc_comment, after_long_help = AFTER_LONG_HELP)]
 pub struct Watch {
@@ -19,7 +34,7 @@ pub struct Watch {
     #[clap(short, long, verbatim_doc_comment, default_value = "default")]
     task: Vec<String>,
 
-    /// Extra arguments
+    /// Extra arguments to pass to the last task
     #[clap(allow_hyphen_values = true)]
     args: Vec<String>,
 
@@ -27,60 +42,84 @@ pub struct Watch {
     /// Defaults to sources from the tasks(s)
     #[clap(short, long, verbatim_doc_comment)]
     glob: Vec<String>,
-    // /// Change to this directory before executing the command
-    // #[clap(short = 'C', long, value_hint = ValueHint::DirPath, long)]
-    // pub cd: Option<PathBuf>,
-    //
-    // /// Don't actually run the tasks(s), just print them in order of execution
-    // #[clap(long, short = 'n', verbatim_doc_comment)]
-    // pub dry_run: bool,
-    //
-    // /// Force the tasks to run even if outputs are up to date
-    // #[clap(long, short, verbatim_doc_comment)]
-    // pub force: bool,
-    //
-    // /// Print stdout/stderr by line, prefixed with the tasks's label
-    // /// Defaults to true if --jobs > 1
-    // /// Configure with `task_output` config or `MISE_TASK_OUTPUT` env var
-    // #[clap(long, short, verbatim_doc_comment, overrides_with = "interleave")]
-    // pub prefix: bool,
-    //
-    // /// Print directly to stdout/stderr instead of by line
-    // /// Defaults to true if --jobs == 1
-    // /// Configure with `task_output` config or `MISE_TASK_OUTPUT` env var
-    // #[clap(long, short, verbatim_doc_comment, overrides_with = "prefix")]
-    // pub interleave: bool,
-    //
-    // /// Tool(s) to also add
-    // /// e.g.: node@20 python@3.10
-    // #[clap(short, long, value_name = "TOOL@VERSION")]
-    // pub tool: Vec<ToolArg>,
-    //
-    // /// Number of tasks to run in parallel
-    // /// [default: 4]
-    // /// Configure with `jobs` config or `MISE_JOBS` env var
-    // #[clap(long, short, env = "MISE_JOBS", verbatim_doc_comment)]
-    // pub jobs: Option<usize>,
-    //
-    // /// Read/write directly to stdin/stdout/stderr instead of by line
-    // /// Configure with `raw` config or `MISE_RAW` env var
-    // #[clap(long, short, verbatim_doc_comment)]
-    // pub raw: bool,
+
+    /// Change to this directory before executing the command
+    #[clap(short = 'C', long, value_hint = ValueHint::DirPath)]
+    pub cd: Option<PathBuf>,
+
+    /// Don't actually run the tasks(s), just print them in order of execution
+    #[clap(long, short = 'n', verbatim_doc_comment)]
+    pub dry_run: bool,
+
+    /// Force the tasks to run even if outputs are up to date
+    #[clap(long, short, verbatim_doc_comment)]
+    pub force: bool,
+
+    /// Print stdout/stderr by line, prefixed with the tasks's label
+    /// Defaults to true if --jobs > 1
+    /// Configure with `task_output` config or `MISE_TASK_OUTPUT` env var
+    #[clap(long, short, verbatim_doc_comment, overrides_with = "interleave")]
+    pub prefix: bool,
+
+    /// Print directly to stdout/stderr instead of by line
+    /// Defaults to true if --jobs == 1
+    /// Configure with `task_output` config or `MISE_TASK_OUTPUT` env var
+    #[clap(long, short, verbatim_doc_comment, overrides_with = "prefix")]
+    pub interleave: bool,
+
+    /// Change how task output is printed
+    /// `json` prints newline-delimited JSON events for task starts, output lines, exit statuses,
+    /// skipped tasks and the dependency graph
+    /// Configure with `task_output` config or `MISE_TASK_OUTPUT` env var
+    #[clap(long, short, verbatim_doc_comment, value_name = "OUTPUT")]
+    pub output: Option<TaskOutput>,
+
+    /// Tool(s) to also add
+    /// e.g.: node@20 python@3.10
+    #[clap(long, value_name = "TOOL@VERSION")]
+    pub tool: Vec<ToolArg>,
+
+    /// Number of tasks to run in parallel
+    /// [default: 4]
+    /// Configure with `jobs` config or `MISE_JOBS` env var
+    #[clap(long, short, env = "MISE_JOBS", verbatim_doc_comment)]
+    pub jobs: Option<usize>,
+
+    /// Read/write directly to stdin/stdout/stderr instead of by line
+    /// Configure with `raw` config or `MISE_RAW` env var
+    #[clap(long, short, verbatim_doc_comment)]
+    pub raw: bool,
+
+    /// Shows elapsed time after each tasks
+    #[clap(long, alias = "timing", verbatim_doc_comment)]
+    pub timings: bool,
+
+    /// Keep running tasks that do not depend on a failed task and report every failure at the end
+    #[clap(long, verbatim_doc_comment)]
+    pub continue_on_error: bool,
+
+    /// Write a JUnit XML report with a testcase for each task to this file
+    /// Task output is captured for the report so tasks will not be connected to stdin
+    #[clap(long, value_hint = ValueHint::FilePath, value_name = "PATH", verbatim_doc_comment)]
+    pub junit: Option<PathBuf>,
+
+    /// Cancel the running tasks and start them again when a change is detected
+    #[clap(long, verbatim_doc_comment)]
+    pub restart: bool,
+
+    /// Wait for files to stop changing for this long before running the tasks
+    #[clap(
+        long,
+        default_value = "50ms",
+        value_parser = humantime::parse_duration,
+        verbatim_doc_comment
+    )]
+    pub debounce: Duration,
 }
 
 impl Watch {
     pub fn run(self) -> Result<()> {
         let config = Config::try_get()?;
-        let ts = ToolsetBuilder::new().build(&config)?;
-        if let Err(err) = which::which("watchexec") {
-            let watchexec: BackendArg = "watchexec".into();
-            if !ts.versions.contains_key(&watchexec) {
-                eprintln!("{}: {}", style("Error").red().bold(), err);
-                eprintln!("{}: Install watchexec with:", style("Hint").bold());
-                eprintln!("  mise use -g watchexec@latest");
-                exit(1);
-            }
-        }
         let tasks = self
             .task
             .iter()
@@ -89,43 +128,247 @@ impl Watch {
                     .tasks_with_aliases()?
                     .get(t)
                     .cloned()
+                    .cloned()
                     .ok_or_else(|| eyre!("Tasks not found: {t}"))
             })
             .collect::<Result<Vec<_>>>()?;
-        let mut args = vec![];
-        let globs = if self.glob.is_empty() {
-            tasks
-                .iter()
-                .flat_map(|t| t.sources.clone())
-                .collect::<Vec<_>>()
+        let watches = self.watches(&config, &tasks)?;
+        if watches.iter().all(|(_, globs)| globs.is_empty()) {
+            warn!("no sources defined on the tasks, use --glob to specify files to watch");
+        }
+
+        let mut watcher = Watcher::new(watches)?;
+        let mut child = self.spawn(&tasks)?;
+        loop {
+            let changed = watcher.wait_for_changes(None, self.debounce);
+            if changed.is_empty() {
+                continue;
+            }
+            trace!("changed: {}", changed.join(" "));
+            self.stop(child)?;
+            child = self.spawn(&tasks)?;
+        }
+    }
+
+    /// cancels the running tasks with `--restart`, otherwise waits for them to finish
+    fn stop(&self, child: Child) -> Result<()> {
+        if self.restart {
+            eprintln!("{}", style::edim("change detected, restarting tasks"));
+            terminate(child)
         } else {
-            self.glob.clone()
+            eprintln!("{}", style::edim("change detected, waiting for tasks"));
+            wait(child)
+        }
+    }
+
+    /// the root directory and globs to watch for each task
+    fn watches(&self, config: &Config, tasks: &[Task]) -> Result<Vec<(PathBuf, Vec<String>)>> {
+        let default_root = match self.cd.as_ref().or(config.project_root.as_ref()) {
+            Some(root) => root.clone(),
+            None => env::current_dir()?,
         };
-        if !globs.is_empty() {
-            args.push("-f".to_string());
-            args.extend(itertools::intersperse(globs, "-f".to_string()).collect::<Vec<_>>());
+        if !self.glob.is_empty() {
+            return Ok(vec![(default_root, self.glob.clone())]);
+        }
+        Ok(tasks
+            .iter()
+            .map(|t| {
+                let root = self.cd.as_ref().or(t.dir.as_ref()).unwrap_or(&default_root);
+                (root.clone(), t.sources.clone())
+            })
+            .collect())
+    }
+
+    fn spawn(&self, tasks: &[Task]) -> Result<Child> {
+        let mut args = vec!["run".to_string()];
+        if let Some(cd) = &self.cd {
+            args.push("--cd".into());
+            args.push(cd.display().to_string());
+        }
+        if self.dry_run {
+            args.push("--dry-run".into());
+        }
+        if self.force {
+            args.push("--force".into());
+        }
+        if self.prefix {
+            args.push("--prefix".into());
+        }
+        if self.interleave {
+            args.push("--interleave".into());
+        }
+        if let Some(output) = self.output {
+            args.push("--output".into());
+            args.push(output.to_string());
+        }
+        for tool in &self.tool {
+            args.push("--tool".into());
+            args.push(tool.to_string());
         }
+        if let Some(jobs) = self.jobs {
+            args.push("--jobs".into());
+            args.push(jobs.to_string());
+        }
+        if self.raw {
+            args.push("--raw".into());
+        }
+        if self.timings {
+            args.push("--timings".into());
+        }
+        if self.continue_on_error {
+            args.push("--continue-on-error".into());
+        }
+        if let Some(junit) = &self.junit {
+            args.push("--junit".into());
+            args.push(junit.display().to_string());
+        }
+        args.extend(
+            itertools::intersperse(tasks.iter().map(|t| t.name.clone()), ":::".to_string())
+                .collect_vec(),
+        );
         args.extend(self.args.clone());
-        args.extend(["--".to_string(), "mise".to_string(), "run".to_string()]);
-        for arg in itertools::intersperse(tasks.iter().map(|t| t.name.as_str()), ":::") {
-            args.push(arg.to_string());
+        debug!("$ mise {}", args.join(" "));
+        Ok(Command::new(&*env::MISE_BIN).args(&args).spawn()?)
+    }
+}
+
+/// watches the roots for changes to files matching the globs. The globs are only expanded again
+/// on events for directories since those can add or remove matching files without an event for
+/// each of them (e.g. a directory moved into the root).
+struct Watcher {
+    watches: Vec<(PathBuf, Vec<String>)>,
+    patterns: Vec<Pattern>,
+    /// the files matched when the globs were last expanded
+    files: BTreeSet<PathBuf>,
+    rx: mpsc::Receiver<notify::Result<Event>>,
+    _watcher: RecommendedWatcher,
+}
+
+impl Watcher {
+    fn new(watches: Vec<(PathBuf, Vec<String>)>) -> Result<Self> {
+        // events are reported with canonical paths, e.g.: /private/var instead of /var on macOS
+        let watches = watches
+            .into_iter()
+            .map(|(root, globs)| (root.canonicalize().unwrap_or(root), globs))
+            .collect_vec();
+        let patterns = watches
+            .iter()
+            .flat_map(|(root, globs)| globs.iter().map(move |g| root.join(g)))
+            .filter_map(|g| Pattern::new(&g.to_string_lossy()).ok())
+            .collect();
+        let (tx, rx) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(tx)?;
+        for root in watches.iter().map(|(root, _)| root).unique() {
+            notify::Watcher::watch(&mut watcher, root, RecursiveMode::Recursive)?;
         }
-        info!("$ watchexec {}", args.join(" "));
-        let mut cmd = cmd::cmd("watchexec", &args);
-        for (k, v) in ts.env_with_path(&config)? {
-            cmd = cmd.env(k, v);
+        let mut watcher = Self {
+            watches,
+            patterns,
+            files: BTreeSet::new(),
+            rx,
+            _watcher: watcher,
+        };
+        watcher.files = watcher.expand();
+        Ok(watcher)
+    }
+
+    fn expand(&self) -> BTreeSet<PathBuf> {
+        self.watches
+            .iter()
+            .flat_map(|(root, globs)| get_source_files(root, globs))
+            .collect()
+    }
+
+    fn is_match(&self, path: &Path) -> bool {
+        let options = MatchOptions {
+            require_literal_separator: true,
+            ..Default::default()
+        };
+        self.patterns
+            .iter()
+            .any(|p| p.matches_path_with(path, options))
+    }
+
+    /// waits for files matching the globs to change (for up to `timeout`) and then to stop
+    /// changing for `debounce`, returning the files that changed
+    fn wait_for_changes(&mut self, timeout: Option<Duration>, debounce: Duration) -> Vec<String> {
+        let mut changed = BTreeSet::new();
+        let mut event = match timeout {
+            Some(timeout) => self.rx.recv_timeout(timeout).ok(),
+            None => self.rx.recv().ok(),
+        };
+        while let Some(e) = event {
+            match e {
+                Ok(e) => changed.extend(self.changed_files(e)),
+                Err(err) => warn!("watch error: {err}"),
+            }
+            event = match changed.is_empty() {
+                true => self.rx.try_recv().ok(),
+                false => self.rx.recv_timeout(debounce).ok(),
+            };
+        }
+        changed.iter().map(|p| p.display().to_string()).collect()
+    }
+
+    fn changed_files(&mut self, event: Event) -> BTreeSet<PathBuf> {
+        if let EventKind::Access(_) = event.kind {
+            return BTreeSet::new();
         }
-        if let Some(root) = &config.project_root {
-            cmd = cmd.dir(root);
+        let is_dir_event = |path: &Path| {
+            matches!(
+                event.kind,
+                EventKind::Create(CreateKind::Folder) | EventKind::Remove(RemoveKind::Folder)
+            ) || path.is_dir()
+                || matches!(event.kind, EventKind::Modify(ModifyKind::Name(_))) && !path.exists()
+        };
+        let mut changed = BTreeSet::new();
+        for path in &event.paths {
+            if self.is_match(path) {
+                match path.is_file() {
+                    true => self.files.insert(path.clone()),
+                    false => self.files.remove(path),
+                };
+                changed.insert(path.clone());
+            } else if is_dir_event(path) {
+                let files = self.expand();
+                changed.extend(files.symmetric_difference(&self.files).cloned());
+                self.files = files;
+            }
         }
-        cmd.run()?;
-        Ok(())
+        changed
+    }
+}
+
+fn wait(mut child: Child) -> Result<()> {
+    child.wait()?;
+    Ok(())
+}
+
+#[cfg(unix)]
+fn terminate(child: Child) -> Result<()> {
+    use nix::sys::signal::{kill, SIGTERM};
+    use nix::unistd::Pid;
+    // `mise run` passes the signal on to the tasks it is running
+    if let Err(e) = kill(Pid::from_raw(child.id() as i32), SIGTERM) {
+        debug!("failed to terminate tasks: {e}");
     }
+    wait(child)
+}
+
+#[cfg(windows)]
+fn terminate(child: Child) -> Result<()> {
+    Command::new("taskkill")
+        .arg("/F")
+        .arg("/T")
+        .arg("/PID")
+        .arg(child.id().to_string())
+        .status()?;
+    wait(child)
 }
 
 static AFTER_LONG_HELP: &str = color_print::cstr!(
     r#"<bold><underline>Examples:</underline></bold>
-    
+
     $ <bold>mise watch -t build</bold>
     Runs the "build" tasks. Will re-run the tasks when any of its sources change.
     Uses "sources" from the tasks definition to determine which files to watch.
@@ -134,7 +377,112 @@ static AFTER_LONG_HELP: &str = color_print::cstr!(
     Runs the "build" tasks but specify the files to watch with a glob pattern.
     This overrides the "sources" from the tasks definition.
 
-    $ <bold>mise run -t build --clear</bold>
-    Extra arguments are passed to watchexec. See `watchexec --help` for details.
+    $ <bold>mise watch -t serve --restart</bold>
+    Runs the "serve" tasks and restarts it whenever its sources change.
+
+    $ <bold>mise watch -t build -- --release</bold>
+    Extra arguments are passed to the last task.
 "#
 );
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+    use crate::file;
+
+    const TIMEOUT: Option<Duration> = Some(Duration::from_secs(5));
+    const DEBOUNCE: Duration = Duration::from_millis(100);
+
+    fn watcher(dir: &Path, glob: &str) -> Watcher {
+        Watcher::new(vec![(dir.to_path_buf(), vec![glob.to_string()])]).unwrap()
+    }
+
+    fn tempdir() -> (tempfile::TempDir, PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().canonicalize().unwrap();
+        (dir, path)
+    }
+
+    #[test]
+    fn test_watcher_modified() {
+        let (_tmp, dir) = tempdir();
+        let a = dir.join("a.txt");
+        file::write(&a, "a").unwrap();
+        let mut watcher = watcher(&dir, "*.txt");
+
+        file::write(&a, "b").unwrap();
+        let changed = watcher.wait_for_changes(TIMEOUT, DEBOUNCE);
+        assert_eq!(changed, vec![a.display().to_string()]);
+    }
+
+    #[test]
+    fn test_watcher_added_file() {
+        let (_tmp, dir) = tempdir();
+        file::write(dir.join("a.txt"), "a").unwrap();
+        let mut watcher = watcher(&dir, "*.txt");
+
+        let b = dir.join("b.txt");
+        file::write(&b, "b").unwrap();
+        assert_eq!(
+            watcher.wait_for_changes(TIMEOUT, DEBOUNCE),
+            vec![b.display().to_string()]
+        );
+    }
+
+    #[test]
+    fn test_watcher_unmatched_file() {
+        let (_tmp, dir) = tempdir();
+        file::create_dir_all(dir.join("sub")).unwrap();
+        let mut watcher = watcher(&dir, "*.txt");
+
+        file::write(dir.join("b.rs"), "b").unwrap();
+        file::write(dir.join("sub/c.txt"), "c").unwrap();
+        let changed = watcher.wait_for_changes(Some(Duration::from_millis(500)), DEBOUNCE);
+        assert_eq!(changed, Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_watcher_new_dir() {
+        let (_tmp, dir) = tempdir();
+        let mut watcher = watcher(&dir, "src/**/*.txt");
+
+        let a = dir.join("src/sub/a.txt");
+        file::create_dir_all(a.parent().unwrap()).unwrap();
+        file::write(&a, "a").unwrap();
+        let start = Instant::now();
+        let mut changed = vec![];
+        while changed.is_empty() && start.elapsed() < TIMEOUT.unwrap() {
+            changed = watcher.wait_for_changes(TIMEOUT, DEBOUNCE);
+        }
+        assert_eq!(changed, vec![a.display().to_string()]);
+    }
+
+    #[test]
+    fn test_watcher_moved_dir() {
+        let (_tmp, dir) = tempdir();
+        let (_other_tmp, other) = tempdir();
+        file::create_dir_all(other.join("src")).unwrap();
+        file::write(other.join("src/a.txt"), "a").unwrap();
+        let mut watcher = watcher(&dir, "src/*.txt");
+
+        // only the directory gets an event
+        file::rename(other.join("src"), dir.join("src")).unwrap();
+        let start = Instant::now();
+        let mut changed = vec![];
+        while changed.is_empty() && start.elapsed() < TIMEOUT.unwrap() {
+            changed = watcher.wait_for_changes(TIMEOUT, DEBOUNCE);
+        }
+        assert_eq!(changed, vec![dir.join("src/a.txt").display().to_string()]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_terminate() {
+        let child = Command::new("sleep").arg("60").spawn().unwrap();
+        let start = Instant::now();
+        terminate(child).unwrap();
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+}
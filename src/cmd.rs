@@ -3,14 +3,15 @@ use std::ffi::{OsStr, OsString};
 use std::fmt::{Display, Formatter};
 use std::io::{BufRead, BufReader, Write};
 use std::path::{Path, PathBuf};
-use std::process::{Command, ExitStatus, Stdio};
+use std::process::{Child, Command, ExitStatus, Stdio};
 use std::sync::mpsc::channel;
 use std::sync::{Mutex, RwLock};
 use std::thread;
+use std::time::Duration;
 
 use color_eyre::Result;
 use duct::{Expression, IntoExecutablePath};
-use eyre::Context;
+use eyre::{eyre, Context};
 use once_cell::sync::Lazy;
 #[cfg(not(any(test, target_os = "windows")))]
 use signal_hook::consts::{SIGHUP, SIGINT, SIGQUIT, SIGTERM, SIGUSR1, SIGUSR2};
@@ -18,11 +19,11 @@ use signal_hook::consts::{SIGHUP, SIGINT, SIGQUIT, SIGTERM, SIGUSR1, SIGUSR2};
 use signal_hook::iterator::Signals;
 
 use crate::config::SETTINGS;
-use crate::env;
 use crate::env::PATH_KEY;
 use crate::errors::Error::ScriptFailed;
 use crate::file::display_path;
 use crate::ui::progress_report::SingleReport;
+use crate::{env, timeout};
 
 /// Create a command with any number of of positional arguments
 ///
@@ -101,13 +102,24 @@ pub struct CmdLineRunner<'a> {
     stdin: Option<String>,
     prefix: String,
     raw: bool,
+    capture: bool,
     pass_signals: bool,
+    on_stdout: Option<Box<dyn Fn(String) + Send + Sync + 'a>>,
+    on_stderr: Option<Box<dyn Fn(String) + Send + Sync + 'a>>,
+    timeout: Option<Duration>,
 }
 
 static OUTPUT_LOCK: Mutex<()> = Mutex::new(());
 
 static RUNNING_PIDS: Lazy<Mutex<HashSet<u32>>> = Lazy::new(Default::default);
 
+/// commands with a timeout run in their own process group so it can be killed with all
+/// of its children, which would otherwise keep stdout/stderr open
+static PROCESS_GROUPS: Lazy<Mutex<HashSet<u32>>> = Lazy::new(Default::default);
+
+/// how long a command that timed out has to exit after SIGTERM before it gets SIGKILL
+const KILL_GRACE_PERIOD: Duration = Duration::from_secs(5);
+
 impl<'a> CmdLineRunner<'a> {
     pub fn new<P: AsRef<OsStr>>(program: P) -> Self {
         let mut cmd = if cfg!(windows) {
@@ -127,17 +139,21 @@ impl<'a> CmdLineRunner<'a> {
             stdin: None,
             prefix: String::new(),
             raw: false,
+            capture: false,
             pass_signals: false,
+            on_stdout: None,
+            on_stderr: None,
+            timeout: None,
         }
     }
 
     #[cfg(unix)]
     pub fn kill_all(signal: nix::sys::signal::Signal) {
         let pids = RUNNING_PIDS.lock().unwrap();
+        let groups = PROCESS_GROUPS.lock().unwrap();
         for pid in pids.iter() {
-            let pid = *pid as i32;
             trace!("{signal}: {pid}");
-            if let Err(e) = nix::sys::signal::kill(nix::unistd::Pid::from_raw(pid), signal) {
+            if let Err(e) = send_signal(*pid, groups.contains(pid), signal) {
                 debug!("Failed to kill cmd {pid}: {e}");
             }
         }
@@ -247,11 +263,36 @@ impl<'a> CmdLineRunner<'a> {
         self.pr = Some(pr);
         self
     }
+
+    /// handle each line of stdout with this function instead of printing it
+    pub fn with_on_stdout<F: Fn(String) + Send + Sync + 'a>(mut self, on_stdout: F) -> Self {
+        self.on_stdout = Some(Box::new(on_stdout));
+        self
+    }
+
+    /// handle each line of stderr with this function instead of printing it
+    pub fn with_on_stderr<F: Fn(String) + Send + Sync + 'a>(mut self, on_stderr: F) -> Self {
+        self.on_stderr = Some(Box::new(on_stderr));
+        self
+    }
+
+    /// kill the command if it is still running after this long
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
     pub fn with_raw(&mut self) -> &mut Self {
         self.raw = true;
         self
     }
 
+    /// always send the output to `on_stdout`/`on_stderr`, even with `raw` enabled
+    pub fn with_capture(mut self) -> Self {
+        self.capture = true;
+        self
+    }
+
     pub fn with_pass_signals(&mut self) -> &mut Self {
         self.pass_signals = true;
         self
@@ -268,17 +309,26 @@ impl<'a> CmdLineRunner<'a> {
         static RAW_LOCK: RwLock<()> = RwLock::new(());
         let read_lock = RAW_LOCK.read().unwrap();
         debug!("$ {self}");
-        if SETTINGS.raw || self.raw {
+        if (SETTINGS.raw || self.raw) && !self.capture {
             drop(read_lock);
             let _write_lock = RAW_LOCK.write().unwrap();
             return self.execute_raw();
         }
+        let group = cfg!(unix) && self.timeout.is_some();
+        #[cfg(unix)]
+        if group {
+            use std::os::unix::process::CommandExt;
+            self.cmd.process_group(0);
+        }
         let mut cp = self
             .cmd
             .spawn()
             .wrap_err_with(|| format!("failed to execute command: {self}"))?;
         let id = cp.id();
         RUNNING_PIDS.lock().unwrap().insert(id);
+        if group {
+            PROCESS_GROUPS.lock().unwrap().insert(id);
+        }
         trace!("Started process: {id} for {}", self.get_program());
         let (tx, rx) = channel();
         if let Some(stdout) = cp.stdout.take() {
@@ -323,8 +373,9 @@ impl<'a> CmdLineRunner<'a> {
                 }
             });
         }
+        let timeout = self.timeout;
         thread::spawn(move || {
-            let status = cp.wait().unwrap();
+            let status = wait_with_timeout(&mut cp, timeout, group).unwrap();
             #[cfg(not(any(test, target_os = "windows")))]
             if let Some(sighandle) = sighandle {
                 sighandle.close();
@@ -352,15 +403,17 @@ impl<'a> CmdLineRunner<'a> {
                 ChildProcessOutput::Signal(sig) => {
                     if sig != SIGINT {
                         debug!("Received signal {sig}, {id}");
-                        let pid = nix::unistd::Pid::from_raw(id as i32);
                         let sig = nix::sys::signal::Signal::try_from(sig).unwrap();
-                        nix::sys::signal::kill(pid, sig)?;
+                        send_signal(id, group, sig)?;
                     }
                 }
             }
         }
         RUNNING_PIDS.lock().unwrap().remove(&id);
-        let status = status.unwrap();
+        PROCESS_GROUPS.lock().unwrap().remove(&id);
+        let Some(status) = status.unwrap() else {
+            return Err(self.timed_out());
+        };
 
         if !status.success() {
             self.on_error(combined_output.join("\n"), status)?;
@@ -370,16 +423,27 @@ impl<'a> CmdLineRunner<'a> {
     }
 
     fn execute_raw(mut self) -> Result<()> {
-        let status = self.cmd.spawn()?.wait()?;
+        // stays in the foreground process group since it may read from the terminal
+        let mut cp = self.cmd.spawn()?;
+        let Some(status) = wait_with_timeout(&mut cp, self.timeout, false)? else {
+            return Err(self.timed_out());
+        };
         match status.success() {
             true => Ok(()),
             false => self.on_error(String::new(), status),
         }
     }
 
+    fn timed_out(&self) -> eyre::Report {
+        let timeout = humantime::format_duration(self.timeout.unwrap_or_default());
+        eyre!("{} timed out after {timeout}", self.get_program())
+    }
+
     fn on_stdout(&self, line: &str) {
         let _lock = OUTPUT_LOCK.lock().unwrap();
-        if let Some(pr) = self.pr {
+        if let Some(on_stdout) = &self.on_stdout {
+            on_stdout(line.into())
+        } else if let Some(pr) = self.pr {
             if !line.trim().is_empty() {
                 pr.set_message(line.into())
             }
@@ -392,6 +456,9 @@ impl<'a> CmdLineRunner<'a> {
 
     fn on_stderr(&self, line: &str) {
         let _lock = OUTPUT_LOCK.lock().unwrap();
+        if let Some(on_stderr) = &self.on_stderr {
+            return on_stderr(line.into());
+        }
         match self.pr {
             Some(pr) => {
                 if !line.trim().is_empty() {
@@ -442,23 +509,135 @@ impl Display for CmdLineRunner<'_> {
     }
 }
 
+/// waits for the process to exit, returning None if it was killed after `timeout`.
+/// `group` kills its process group instead of only the process itself.
+fn wait_with_timeout(
+    cp: &mut Child,
+    timeout: Option<Duration>,
+    group: bool,
+) -> Result<Option<ExitStatus>> {
+    let Some(timeout) = timeout else {
+        return Ok(Some(cp.wait()?));
+    };
+    let id = cp.id();
+    let mut timed_out = false;
+    let status = timeout::run_with_timeout_or_else(
+        || Ok(cp.wait()?),
+        timeout,
+        || {
+            debug!("timed out, killing {id}");
+            timed_out = true;
+            kill(id, group);
+        },
+    );
+    match timed_out {
+        true => Ok(None),
+        false => Ok(Some(status?)),
+    }
+}
+
+#[cfg(unix)]
+fn send_signal(pid: u32, group: bool, signal: nix::sys::signal::Signal) -> nix::Result<()> {
+    let pid = nix::unistd::Pid::from_raw(pid as i32);
+    match group {
+        true => nix::sys::signal::killpg(pid, signal),
+        false => nix::sys::signal::kill(pid, signal),
+    }
+}
+
+/// sends SIGTERM, then SIGKILL if the process (group) is still around after `KILL_GRACE_PERIOD`
+#[cfg(unix)]
+fn kill(pid: u32, group: bool) {
+    use nix::sys::signal::{SIGKILL, SIGTERM};
+    if let Err(e) = send_signal(pid, group, SIGTERM) {
+        debug!("Failed to kill cmd {pid}: {e}");
+        return;
+    }
+    let is_running = || {
+        let pid = nix::unistd::Pid::from_raw(pid as i32);
+        match group {
+            true => nix::sys::signal::killpg(pid, None).is_ok(),
+            // the process is reaped by `wait_with_timeout` once it exits
+            false => nix::sys::signal::kill(pid, None).is_ok(),
+        }
+    };
+    let start = std::time::Instant::now();
+    while is_running() {
+        if start.elapsed() > KILL_GRACE_PERIOD {
+            debug!("{pid} did not exit after SIGTERM, sending SIGKILL");
+            if let Err(e) = send_signal(pid, group, SIGKILL) {
+                debug!("Failed to kill cmd {pid}: {e}");
+            }
+            return;
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+}
+
+#[cfg(windows)]
+fn kill(pid: u32, _group: bool) {
+    if let Err(e) = Command::new("taskkill")
+        .arg("/F")
+        .arg("/T")
+        .arg("/PID")
+        .arg(pid.to_string())
+        .spawn()
+    {
+        warn!("Failed to kill cmd {pid}: {e}");
+    }
+}
+
 enum ChildProcessOutput {
     Stdout(String),
     Stderr(String),
-    ExitStatus(ExitStatus),
+    ExitStatus(Option<ExitStatus>),
     #[cfg(not(any(test, target_os = "windows")))]
     Signal(i32),
 }
 
 #[cfg(test)]
 mod tests {
+    use std::time::{Duration, Instant};
+
     use pretty_assertions::assert_eq;
 
     use crate::cmd;
 
+    use super::*;
+
     #[test]
     fn test_cmd() {
         let output = cmd!("echo", "foo", "bar").read().unwrap();
         assert_eq!("foo bar", output);
     }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_timeout_kills_children() {
+        let start = Instant::now();
+        let err = CmdLineRunner::new("sh")
+            .arg("-c")
+            .arg("sleep 100 & sleep 100")
+            .with_on_stdout(|_| {})
+            .with_timeout(Duration::from_millis(200))
+            .execute()
+            .unwrap_err();
+        assert_eq!(err.to_string(), "sh timed out after 200ms");
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_timeout_kills_after_sigterm() {
+        let start = Instant::now();
+        let err = CmdLineRunner::new("sh")
+            .arg("-c")
+            .arg("trap '' TERM; sleep 100")
+            .with_on_stdout(|_| {})
+            .with_timeout(Duration::from_millis(200))
+            .execute()
+            .unwrap_err();
+        assert_eq!(err.to_string(), "sh timed out after 200ms");
+        assert!(start.elapsed() < KILL_GRACE_PERIOD + Duration::from_secs(5));
+    }
 }
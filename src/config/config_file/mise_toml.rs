@@ -13,15 +13,16 @@ use tera::Context as TeraContext;
 use toml_edit::{table, value, Array, DocumentMut, Item, Value};
 use versions::Versioning;
 
+use crate::backend::BackendType;
 use crate::cli::args::{BackendArg, ToolVersionType};
 use crate::config::config_file::toml::{deserialize_arr, deserialize_path_entry_arr};
 use crate::config::config_file::{trust_check, ConfigFile, TaskConfig};
-use crate::config::env_directive::{EnvDirective, PathEntry};
+use crate::config::env_directive::{EnvDirective, EnvVarSchema, PathEntry, SourceEntry};
 use crate::config::settings::SettingsPartial;
 use crate::config::AliasMap;
 use crate::file::{create_dir_all, display_path};
 use crate::task::Task;
-use crate::tera::{get_tera, BASE_CONTEXT};
+use crate::tera::{get_tera_for_config, BASE_CONTEXT};
 use crate::toolset::{ToolRequest, ToolRequestSet, ToolSource, ToolVersionOptions};
 use crate::{dirs, file};
 
@@ -29,6 +30,10 @@ use crate::{dirs, file};
 pub struct MiseToml {
     #[serde(default, deserialize_with = "deserialize_version")]
     min_version: Option<Versioning>,
+    #[serde(default)]
+    root: bool,
+    #[serde(default, deserialize_with = "deserialize_arr")]
+    extends: Vec<String>,
     #[serde(skip)]
     context: TeraContext,
     #[serde(skip)]
@@ -184,12 +189,11 @@ impl MiseToml {
     }
 
     fn parse_template(&self, input: &str) -> eyre::Result<String> {
-        if !input.contains("{{") && !input.contains("{%") && !input.contains("{#") {
+        if !is_template(input) {
             return Ok(input.to_string());
         }
         trust_check(&self.path)?;
-        let dir = self.path.parent();
-        let output = get_tera(dir)
+        let output = get_tera_for_config(&self.path)
             .render_str(input, &self.context)
             .wrap_err_with(|| {
                 let p = display_path(&self.path);
@@ -334,8 +338,13 @@ impl ConfigFile for MiseToml {
                 }
                 let version = self.parse_template(&tool.tt.to_string())?;
                 if let Some(mut options) = tool.options.clone() {
-                    for v in options.values_mut() {
-                        *v = self.parse_template(v)?;
+                    // http options are templates rendered with the version at install time
+                    if fa.backend_type != BackendType::Http {
+                        for v in options.values_mut() {
+                            *v = self.parse_template(v)?;
+                        }
+                    } else if options.values().any(|v| is_template(v)) {
+                        trust_check(&self.path)?;
                     }
                     let tvr = ToolRequest::new_opts(fa.clone(), &version, options, source.clone())?;
                     trs.add_version(tvr, &source);
@@ -371,6 +380,14 @@ impl ConfigFile for MiseToml {
         &self.task_config
     }
 
+    fn is_root(&self) -> bool {
+        self.root
+    }
+
+    fn extends(&self) -> &[String] {
+        &self.extends
+    }
+
     fn clone_box(&self) -> Box<dyn ConfigFile> {
         Box::new(self.clone())
     }
@@ -384,6 +401,12 @@ impl Debug for MiseToml {
         if let Some(min_version) = &self.min_version {
             d.field("min_version", &min_version.to_string());
         }
+        if self.root {
+            d.field("root", &self.root);
+        }
+        if !self.extends.is_empty() {
+            d.field("extends", &self.extends);
+        }
         if !self.env_file.is_empty() {
             d.field("env_file", &self.env_file);
         }
@@ -409,6 +432,8 @@ impl Clone for MiseToml {
     fn clone(&self) -> Self {
         Self {
             min_version: self.min_version.clone(),
+            root: self.root,
+            extends: self.extends.clone(),
             context: self.context.clone(),
             path: self.path.clone(),
             env_file: self.env_file.clone(),
@@ -490,8 +515,10 @@ impl<'de> de::Deserialize<'de> for EnvList {
                                 path: Vec<PathEntry>,
                                 #[serde(default, deserialize_with = "deserialize_arr")]
                                 file: Vec<PathBuf>,
+                                #[serde(default, deserialize_with = "deserialize_path_entry_arr")]
+                                source: Vec<SourceEntry>,
                                 #[serde(default, deserialize_with = "deserialize_arr")]
-                                source: Vec<PathBuf>,
+                                secrets: Vec<PathBuf>,
                                 #[serde(default)]
                                 python: EnvDirectivePython,
                                 #[serde(flatten)]
@@ -576,6 +603,9 @@ impl<'de> de::Deserialize<'de> for EnvList {
                             for source in directives.source {
                                 env.push(EnvDirective::Source(source));
                             }
+                            for secrets in directives.secrets {
+                                env.push(EnvDirective::Secrets(secrets));
+                            }
                             for (key, value) in directives.other {
                                 env.push(EnvDirective::Module(key, value));
                             }
@@ -591,6 +621,7 @@ impl<'de> de::Deserialize<'de> for EnvList {
                                 Int(i64),
                                 Str(String),
                                 Bool(bool),
+                                Schema(EnvVarSchema),
                             }
 
                             impl<'de> de::Deserialize<'de> for Val {
@@ -602,7 +633,7 @@ impl<'de> de::Deserialize<'de> for EnvList {
                                 {
                                     struct ValVisitor;
 
-                                    impl Visitor<'_> for ValVisitor {
+                                    impl<'de> Visitor<'de> for ValVisitor {
                                         type Value = Val;
                                         fn expecting(
                                             &self,
@@ -637,6 +668,18 @@ impl<'de> de::Deserialize<'de> for EnvList {
                                         {
                                             Ok(Val::Str(v.to_string()))
                                         }
+
+                                        fn visit_map<M>(
+                                            self,
+                                            map: M,
+                                        ) -> Result<Self::Value, M::Error>
+                                        where
+                                            M: de::MapAccess<'de>,
+                                        {
+                                            Ok(Val::Schema(de::Deserialize::deserialize(
+                                                de::value::MapAccessDeserializer::new(map),
+                                            )?))
+                                        }
                                     }
 
                                     deserializer.deserialize_any(ValVisitor)
@@ -652,6 +695,7 @@ impl<'de> de::Deserialize<'de> for EnvList {
                                     env.push(EnvDirective::Val(key, s));
                                 }
                                 Val::Bool(_b) => env.push(EnvDirective::Rm(key)),
+                                Val::Schema(schema) => env.push(EnvDirective::Schema(key, schema)),
                             }
                         }
                     }
@@ -908,9 +952,13 @@ where
     deserializer.deserialize_map(AliasMapVisitor)
 }
 
+fn is_template(input: &str) -> bool {
+    input.contains("{{") || input.contains("{%") || input.contains("{#")
+}
+
 #[cfg(test)]
 mod tests {
-    use indoc::formatdoc;
+    use indoc::{formatdoc, indoc};
     use insta::{assert_debug_snapshot, assert_snapshot};
     use test_log::test;
 
@@ -963,6 +1011,23 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_env_schema() {
+        reset();
+        let env = parse_env(
+            indoc! {r#"
+            [env]
+            DATABASE_URL = { required = true, description = "postgres url", regex = "^postgres://" }
+            PORT = { type = "integer", default = "8080" }
+            "#}
+            .to_string(),
+        );
+        assert_snapshot!(env, @r###"
+        schema DATABASE_URL required
+        schema PORT default=8080
+        "###);
+    }
+
     #[test]
     fn test_env_array_valid() {
         reset();
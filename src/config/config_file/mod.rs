@@ -86,6 +86,14 @@ pub trait ConfigFile: Debug + Send + Sync {
         static DEFAULT_TASK_CONFIG: Lazy<TaskConfig> = Lazy::new(TaskConfig::default);
         &DEFAULT_TASK_CONFIG
     }
+    /// `root = true` stops looking for config files in parent directories
+    fn is_root(&self) -> bool {
+        false
+    }
+    /// config files this one is based on, see `Config::load`
+    fn extends(&self) -> &[String] {
+        &[]
+    }
     fn clone_box(&self) -> Box<dyn ConfigFile>;
 }
 
@@ -364,6 +372,10 @@ impl Hash for dyn ConfigFile {
 #[derive(Clone, Debug, Default, Deserialize)]
 pub struct TaskConfig {
     pub includes: Option<Vec<PathBuf>>,
+    /// package.json, Makefile or justfile to import tasks from
+    pub imports: Option<Vec<PathBuf>>,
+    /// marks the directory of this config as the root of a monorepo, see `Config::monorepo_tasks`
+    pub monorepo: Option<bool>,
 }
 
 #[cfg(test)]
@@ -36,6 +36,9 @@ impl<'a> TomlParser<'a> {
     pub fn parse_bool(&self, key: &str) -> Option<bool> {
         self.table.get(key).and_then(|value| value.as_bool())
     }
+    pub fn parse_int(&self, key: &str) -> Option<i64> {
+        self.table.get(key).and_then(|value| value.as_integer())
+    }
     pub fn parse_array<T>(&self, key: &str) -> eyre::Result<Option<Vec<T>>>
     where
         T: Default + From<String>,
@@ -51,6 +54,18 @@ impl<'a> TomlParser<'a> {
             })
             .transpose()
     }
+    pub fn parse_table_str(&self, key: &str) -> eyre::Result<Option<BTreeMap<String, String>>> {
+        self.table
+            .get(key)
+            .and_then(|value| value.as_table())
+            .map(|table| {
+                table
+                    .iter()
+                    .filter_map(|(k, v)| v.as_str().map(|v| Ok((k.clone(), self.render_tmpl(v)?))))
+                    .collect::<eyre::Result<BTreeMap<String, String>>>()
+            })
+            .transpose()
+    }
     pub fn parse_env(
         &self,
         key: &str,
@@ -172,6 +187,14 @@ where
             }
             Ok(v)
         }
+
+        fn visit_map<M>(self, map: M) -> std::result::Result<Self::Value, M::Error>
+        where
+            M: de::MapAccess<'de>,
+        {
+            let v = T::deserialize(de::value::MapAccessDeserializer::new(map))?;
+            Ok(vec![v])
+        }
     }
 
     deserializer.deserialize_any(PathEntryArrVisitor(std::marker::PhantomData))
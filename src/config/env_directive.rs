@@ -2,21 +2,26 @@ use std::collections::{BTreeSet, HashMap};
 use std::fmt::{Debug, Display, Formatter};
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::time::Duration;
 
-use eyre::{eyre, Context};
+use eyre::{bail, eyre, Context};
 use indexmap::IndexMap;
-use serde::{Deserialize, Deserializer};
+use itertools::Itertools;
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize};
 
+use crate::cache::CacheManagerBuilder;
 use crate::cmd::CmdLineRunner;
 use crate::config::config_file::trust_check;
 use crate::config::{Config, SETTINGS};
 use crate::env::PATH_KEY;
-use crate::env_diff::{EnvDiff, EnvDiffOperation};
+use crate::env_diff::{EnvDiff, EnvDiffOperation, SourceShell};
 use crate::file::{display_path, which_non_pristine};
+use crate::hash::hash_to_str;
 use crate::plugins::vfox_plugin::VfoxPlugin;
-use crate::tera::{get_tera, BASE_CONTEXT};
+use crate::tera::{get_tera_for_config, BASE_CONTEXT};
 use crate::toolset::ToolsetBuilder;
-use crate::{dirs, env};
+use crate::{dirs, env, env_diff, secrets};
 
 #[derive(Debug, Clone)]
 pub enum PathEntry {
@@ -71,6 +76,136 @@ impl<'de> Deserialize<'de> for PathEntry {
     }
 }
 
+/// a script for `env._.source`, e.g.: `{ path = "env.fish", shell = "fish", timeout = "5s" }`
+#[derive(Debug, Clone)]
+pub struct SourceEntry {
+    pub path: PathBuf,
+    /// defaults to the shell for the extension of the script or bash
+    pub shell: Option<SourceShell>,
+    pub timeout: Option<Duration>,
+    /// reuse the result for this long instead of running the script every time
+    pub cache: Option<Duration>,
+}
+
+impl FromStr for SourceEntry {
+    type Err = eyre::Error;
+
+    fn from_str(s: &str) -> eyre::Result<Self> {
+        Ok(Self {
+            path: PathBuf::from_str(s)?,
+            shell: None,
+            timeout: None,
+            cache: None,
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for SourceEntry {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Debug, Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct MapSourceEntry {
+            path: PathBuf,
+            shell: Option<SourceShell>,
+            timeout: Option<String>,
+            cache: Option<String>,
+        }
+
+        #[derive(Debug, Deserialize)]
+        #[serde(untagged)]
+        enum Helper {
+            Path(PathBuf),
+            Map(MapSourceEntry),
+        }
+
+        Ok(match Helper::deserialize(deserializer)? {
+            Helper::Path(path) => Self {
+                path,
+                shell: None,
+                timeout: None,
+                cache: None,
+            },
+            Helper::Map(this) => {
+                let parse = |d: Option<String>| {
+                    d.map(|d| humantime::parse_duration(&d))
+                        .transpose()
+                        .map_err(serde::de::Error::custom)
+                };
+                Self {
+                    path: this.path,
+                    shell: this.shell,
+                    timeout: parse(this.timeout)?,
+                    cache: parse(this.cache)?,
+                }
+            }
+        })
+    }
+}
+
+/// an env var declared with a table in `[env]`, e.g.:
+/// `DATABASE_URL = { required = true, regex = "^postgres://" }`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EnvVarSchema {
+    #[serde(default)]
+    pub required: bool,
+    pub description: Option<String>,
+    pub default: Option<String>,
+    pub regex: Option<String>,
+    #[serde(rename = "type")]
+    pub var_type: Option<EnvVarType>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, strum::Display)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum EnvVarType {
+    String,
+    Integer,
+    Number,
+    Boolean,
+    Url,
+    Path,
+}
+
+impl EnvVarSchema {
+    /// returns a description of the problem if `value` does not satisfy the schema
+    pub fn validate(&self, key: &str, value: Option<&str>) -> eyre::Result<Option<String>> {
+        let value = match value {
+            Some(value) if !value.is_empty() => value,
+            _ if self.required => {
+                return Ok(Some(match &self.description {
+                    Some(description) => format!("{key} is required: {description}"),
+                    None => format!("{key} is required"),
+                }))
+            }
+            _ => return Ok(None),
+        };
+        if let Some(var_type) = self.var_type {
+            let valid = match var_type {
+                EnvVarType::String => true,
+                EnvVarType::Integer => value.parse::<i64>().is_ok(),
+                EnvVarType::Number => value.parse::<f64>().is_ok(),
+                EnvVarType::Boolean => ["true", "false", "1", "0", "yes", "no"]
+                    .contains(&value.to_lowercase().as_str()),
+                EnvVarType::Url => url::Url::parse(value).is_ok(),
+                EnvVarType::Path => Path::new(value).exists(),
+            };
+            if !valid {
+                return Ok(Some(format!("{key} is not a valid {var_type}")));
+            }
+        }
+        if let Some(regex) = &self.regex {
+            let re = Regex::new(regex)
+                .wrap_err_with(|| format!("invalid regex for env var {key}: {regex}"))?;
+            if !re.is_match(value) {
+                return Ok(Some(format!("{key} does not match {regex}")));
+            }
+        }
+        Ok(None)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum EnvDirective {
     /// simple key/value pair
@@ -81,8 +216,12 @@ pub enum EnvDirective {
     File(PathBuf),
     /// add a path to the PATH
     Path(PathEntry),
-    /// run a bash script and apply the resulting env diff
-    Source(PathBuf),
+    /// run a script with bash or another shell and apply the resulting env diff
+    Source(SourceEntry),
+    /// age or sops encrypted dotenv, json or yaml file
+    Secrets(PathBuf),
+    /// required and/or validated var with an optional default
+    Schema(String, EnvVarSchema),
     PythonVenv {
         path: PathBuf,
         create: bool,
@@ -109,7 +248,24 @@ impl Display for EnvDirective {
             EnvDirective::Rm(k) => write!(f, "unset {k}"),
             EnvDirective::File(path) => write!(f, "dotenv {}", display_path(path)),
             EnvDirective::Path(path) => write!(f, "path_add {}", display_path(path)),
-            EnvDirective::Source(path) => write!(f, "source {}", display_path(path)),
+            EnvDirective::Source(entry) => {
+                write!(f, "source {}", display_path(&entry.path))?;
+                if let Some(shell) = entry.shell {
+                    write!(f, " shell={shell}")?;
+                }
+                Ok(())
+            }
+            EnvDirective::Secrets(path) => write!(f, "secrets {}", display_path(path)),
+            EnvDirective::Schema(k, schema) => {
+                write!(f, "schema {k}")?;
+                if schema.required {
+                    write!(f, " required")?;
+                }
+                if let Some(default) = &schema.default {
+                    write!(f, " default={default}")?;
+                }
+                Ok(())
+            }
             EnvDirective::Module(name, _) => write!(f, "module {}", name),
             EnvDirective::PythonVenv { path, create } => {
                 write!(f, "python venv path={}", display_path(path))?;
@@ -128,6 +284,23 @@ pub struct EnvResults {
     pub env_files: Vec<PathBuf>,
     pub env_paths: Vec<PathBuf>,
     pub env_scripts: Vec<PathBuf>,
+    /// vars that must not be displayed, e.g.: from `env._.secrets`
+    pub redactions: BTreeSet<String>,
+    /// vars declared with a table in `[env]` and the config that declared them
+    pub schemas: IndexMap<String, (EnvVarSchema, PathBuf)>,
+    /// every change made to each var in the order they were applied
+    pub provenance: IndexMap<String, Vec<EnvProvenance>>,
+}
+
+/// a change made to an env var while resolving, see `mise env --explain`
+#[derive(Debug, Clone, Serialize)]
+pub struct EnvProvenance {
+    /// the config file with the directive that made the change
+    pub config: PathBuf,
+    /// e.g.: `dotenv ~/src/proj/.env`, None for `KEY = "value"` and `KEY = false`
+    pub directive: Option<String>,
+    /// None if the var was removed
+    pub value: Option<String>,
 }
 
 impl EnvResults {
@@ -147,6 +320,9 @@ impl EnvResults {
             env_files: Vec::new(),
             env_paths: Vec::new(),
             env_scripts: Vec::new(),
+            redactions: BTreeSet::new(),
+            schemas: IndexMap::new(),
+            provenance: IndexMap::new(),
         };
         let normalize_path = |config_root: &PathBuf, p: PathBuf| {
             let p = p.strip_prefix("./").unwrap_or(&p);
@@ -181,9 +357,11 @@ impl EnvResults {
                     let v = r.parse_template(&ctx, &source, &v)?;
                     r.env_remove.remove(&k);
                     // trace!("resolve: inserting {:?}={:?} from {:?}", &k, &v, &source);
+                    r.record(&k, Some(&v), &source, None);
                     env.insert(k, (v, Some(source.clone())));
                 }
                 EnvDirective::Rm(k) => {
+                    r.record(&k, None, &source, None);
                     env.shift_remove(&k);
                     r.env_remove.insert(k);
                 }
@@ -220,24 +398,31 @@ impl EnvResults {
                         for item in dotenvy::from_path_iter(&p).wrap_err_with(errfn)? {
                             let (k, v) = item.wrap_err_with(errfn)?;
                             r.env_remove.remove(&k);
+                            let directive = format!("dotenv {}", display_path(&p));
+                            r.record(&k, Some(&v), &source, Some(directive));
                             env.insert(k, (v, Some(p.clone())));
                         }
                     }
                 }
-                EnvDirective::Source(input) => {
+                EnvDirective::Source(entry) => {
                     SETTINGS.ensure_experimental("env._.source")?;
                     trust_check(&source)?;
-                    let s = r.parse_template(&ctx, &source, input.to_string_lossy().as_ref())?;
+                    let input = entry.path.to_string_lossy();
+                    let s = r.parse_template(&ctx, &source, input.as_ref())?;
                     for p in xx::file::glob(normalize_path(&config_root, s.into()))? {
                         r.env_scripts.push(p.clone());
-                        let env_diff = EnvDiff::from_bash_script(&p, env_vars.clone())?;
+                        let shell = entry.shell.unwrap_or_else(|| SourceShell::from_script(&p));
+                        let env_diff = source_script(&p, shell, &entry, &env_vars)?;
+                        let directive = format!("source {}", display_path(&p));
                         for p in env_diff.to_patches() {
                             match p {
                                 EnvDiffOperation::Add(k, v) | EnvDiffOperation::Change(k, v) => {
                                     r.env_remove.remove(&k);
+                                    r.record(&k, Some(&v), &source, Some(directive.clone()));
                                     env.insert(k.clone(), (v.clone(), Some(source.clone())));
                                 }
                                 EnvDiffOperation::Remove(k) => {
+                                    r.record(&k, None, &source, Some(directive.clone()));
                                     env.shift_remove(&k);
                                     r.env_remove.insert(k);
                                 }
@@ -245,6 +430,31 @@ impl EnvResults {
                         }
                     }
                 }
+                EnvDirective::Secrets(input) => {
+                    trust_check(&source)?;
+                    let s = r.parse_template(&ctx, &source, input.to_string_lossy().as_ref())?;
+                    for p in xx::file::glob(normalize_path(&config_root, s.into()))? {
+                        r.env_files.push(p.clone());
+                        for (k, v) in secrets::decrypt(&p)? {
+                            r.env_remove.remove(&k);
+                            r.redactions.insert(k.clone());
+                            let directive = format!("secrets {}", display_path(&p));
+                            r.record(&k, Some(&v), &source, Some(directive));
+                            env.insert(k, (v, Some(p.clone())));
+                        }
+                    }
+                }
+                EnvDirective::Schema(k, schema) => {
+                    if let Some(default) = &schema.default {
+                        if !env.contains_key(&k) {
+                            let v = r.parse_template(&ctx, &source, default)?;
+                            r.env_remove.remove(&k);
+                            r.record(&k, Some(&v), &source, Some("default".into()));
+                            env.insert(k.clone(), (v, Some(source.clone())));
+                        }
+                    }
+                    r.schemas.insert(k, (schema, source));
+                }
                 EnvDirective::PythonVenv { path, create } => {
                     trace!("python venv: {} create={create}", display_path(&path));
                     trust_check(&source)?;
@@ -288,6 +498,8 @@ impl EnvResults {
                     }
                     if venv.exists() {
                         r.env_paths.insert(0, venv.join("bin"));
+                        let v = venv.to_string_lossy().to_string();
+                        r.record("VIRTUAL_ENV", Some(&v), &source, Some("python venv".into()));
                         env.insert(
                             "VIRTUAL_ENV".into(),
                             (venv.to_string_lossy().to_string(), Some(source.clone())),
@@ -302,9 +514,11 @@ impl EnvResults {
                     }
                 }
                 EnvDirective::Module(name, value) => {
+                    let directive = format!("module {name}");
                     let plugin = VfoxPlugin::new(name);
                     if let Some(env) = plugin.mise_env(&value)? {
                         for (k, v) in env {
+                            r.record(&k, Some(&v), &source, Some(directive.clone()));
                             r.env.insert(k, (v, source.clone()));
                         }
                     }
@@ -321,6 +535,14 @@ impl EnvResults {
             .map(|(k, (v, _))| (k.clone(), v.clone()))
             .collect::<HashMap<_, _>>();
         ctx.insert("env", &env_vars);
+        let errors = r.validate(&env_vars)?;
+        if !errors.is_empty() {
+            let msg = format!("missing or invalid env vars:\n{}", errors.join("\n"));
+            if validates_strictly() {
+                bail!(msg);
+            }
+            warn!("{msg}");
+        }
         for (k, (v, source)) in env {
             if let Some(source) = source {
                 r.env.insert(k, (v, source));
@@ -349,6 +571,36 @@ impl EnvResults {
         Ok(r)
     }
 
+    fn record(&mut self, key: &str, value: Option<&str>, config: &Path, directive: Option<String>) {
+        self.provenance
+            .entry(key.to_string())
+            .or_default()
+            .push(EnvProvenance {
+                config: config.to_path_buf(),
+                directive,
+                value: value.map(String::from),
+            });
+    }
+
+    /// checks the vars declared with a table in `[env]` against the resolved env
+    pub fn validate(&self, env: &HashMap<String, String>) -> eyre::Result<Vec<String>> {
+        let mut errors = vec![];
+        for (k, (schema, source)) in &self.schemas {
+            if let Some(err) = schema.validate(k, env.get(k).map(|v| v.as_str()))? {
+                errors.push(format!("  {err} (declared in {})", display_path(source)));
+            }
+        }
+        Ok(errors)
+    }
+
+    /// hides the values of vars from `env._.secrets` for display
+    pub fn redact<'a>(&self, key: &str, value: &'a str) -> &'a str {
+        match self.redactions.contains(key) {
+            true => "[redacted]",
+            false => value,
+        }
+    }
+
     fn parse_template(
         &self,
         ctx: &tera::Context,
@@ -359,19 +611,58 @@ impl EnvResults {
             return Ok(input.to_string());
         }
         trust_check(path)?;
-        let dir = path.parent();
-        let output = get_tera(dir)
+        let output = get_tera_for_config(path)
             .render_str(input, ctx)
             .wrap_err_with(|| eyre!("failed to parse template: '{input}'"))?;
         Ok(output)
     }
 }
 
+/// runs an `env._.source` script, if `cache` is set the result is reused for that long or until
+/// the script or its input env changes
+fn source_script(
+    script: &Path,
+    shell: SourceShell,
+    entry: &SourceEntry,
+    env: &HashMap<String, String>,
+) -> eyre::Result<EnvDiff> {
+    let run = || {
+        debug!("source: running {} with {shell}", display_path(script));
+        EnvDiff::from_script(script, shell, env.clone(), entry.timeout)
+    };
+    let Some(ttl) = entry.cache else {
+        return run();
+    };
+    // __MISE_* vars are excluded since hook-env changes them on every prompt
+    let input = env
+        .iter()
+        .filter(|(k, _)| !env_diff::is_ignored_key(k) && !k.starts_with("__MISE_"))
+        .sorted()
+        .collect_vec();
+    let key = hash_to_str(&(script, shell, input));
+    let cache = CacheManagerBuilder::new(
+        dirs::CACHE
+            .join("env-source")
+            .join(format!("{key}.msgpack.z")),
+    )
+    .with_fresh_duration(Some(ttl))
+    .with_fresh_file(script.to_path_buf())
+    // sourced scripts often export tokens
+    .with_private()
+    .build();
+    Ok(cache.get_or_try_init(run)?.clone())
+}
+
 impl Debug for EnvResults {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         let mut ds = f.debug_struct("EnvResults");
         if !self.env.is_empty() {
-            ds.field("env", &self.env);
+            let env = self
+                .env
+                .iter()
+                .map(|(k, (v, source))| (k, (self.redact(k, v), source)))
+                .collect::<IndexMap<_, _>>();
+            ds.field("env", &env);
         }
         if !self.env_remove.is_empty() {
             ds.field("env_remove", &self.env_remove);
@@ -385,13 +676,35 @@ impl Debug for EnvResults {
         if !self.env_scripts.is_empty() {
             ds.field("env_scripts", &self.env_scripts);
         }
+        if !self.schemas.is_empty() {
+            ds.field("schemas", &self.schemas.keys().collect_vec());
+        }
         ds.finish()
     }
 }
 
+/// only commands that run something with the env and `mise doctor` fail on missing or invalid
+/// vars, failing the others, e.g.: hook-env, would leave the shell without any of the env.
+/// Outside of the cli, e.g.: in tests, the env is always validated strictly.
+fn validates_strictly() -> bool {
+    // tests share SUBCOMMANDS with the cli commands run by other tests
+    if cfg!(test) {
+        return true;
+    }
+    let subcommands = env::SUBCOMMANDS.read().unwrap();
+    matches!(
+        subcommands
+            .iter()
+            .map(|s| s.as_str())
+            .collect_vec()
+            .as_slice(),
+        [] | ["doctor" | "exec" | "run" | "env" | "watch", ..] | ["tasks", "run", ..]
+    )
+}
+
 #[cfg(test)]
 mod tests {
-    use insta::assert_debug_snapshot;
+    use insta::{assert_debug_snapshot, assert_snapshot};
     use test_log::test;
 
     use crate::test::{replace_path, reset};
@@ -475,4 +788,65 @@ mod tests {
         "###
         );
     }
+
+    #[test]
+    fn test_env_schema() {
+        reset();
+        let schema = |toml: &str| -> EnvVarSchema { toml::from_str(toml).unwrap() };
+        let env = HashMap::from([
+            ("DATABASE_URL".to_string(), "mysql://localhost".to_string()),
+            ("PORT".to_string(), "80a".to_string()),
+        ]);
+        let results = EnvResults::resolve(
+            &env,
+            vec![
+                (
+                    EnvDirective::Schema("TOKEN".into(), schema("required = true")),
+                    PathBuf::from("/config"),
+                ),
+                (
+                    EnvDirective::Schema(
+                        "DATABASE_URL".into(),
+                        schema(r#"regex = "^postgres://""#),
+                    ),
+                    PathBuf::from("/config"),
+                ),
+                (
+                    EnvDirective::Schema("PORT".into(), schema(r#"type = "integer""#)),
+                    PathBuf::from("/config"),
+                ),
+                (
+                    EnvDirective::Schema(
+                        "HOST".into(),
+                        schema(
+                            r#"required = true
+                            default = "localhost""#,
+                        ),
+                    ),
+                    PathBuf::from("/config"),
+                ),
+            ],
+        );
+        assert_snapshot!(results.unwrap_err(), @r###"
+        missing or invalid env vars:
+          TOKEN is required (declared in /config)
+          DATABASE_URL does not match ^postgres:// (declared in /config)
+          PORT is not a valid integer (declared in /config)
+        "###);
+        let results = EnvResults::resolve(
+            &HashMap::from([("TOKEN".to_string(), "abc".to_string())]),
+            vec![(
+                EnvDirective::Schema(
+                    "HOST".into(),
+                    schema(
+                        r#"required = true
+                        default = "{{ env.TOKEN }}.localhost""#,
+                    ),
+                ),
+                Default::default(),
+            )],
+        )
+        .unwrap();
+        assert_eq!(results.env["HOST"].0, "abc.localhost");
+    }
 }
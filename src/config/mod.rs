@@ -4,10 +4,13 @@ use std::iter::once;
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, OnceLock, RwLock};
 
+use either::Either;
 use eyre::{ensure, eyre, Context, Result};
+use ignore::WalkBuilder;
 use indexmap::IndexMap;
 use itertools::Itertools;
 use once_cell::sync::{Lazy, OnceCell};
+use path_absolutize::Absolutize;
 use rayon::prelude::*;
 pub use settings::Settings;
 use walkdir::WalkDir;
@@ -18,11 +21,14 @@ use crate::cli::version;
 use crate::config::config_file::legacy_version::LegacyVersionFile;
 use crate::config::config_file::mise_toml::MiseToml;
 use crate::config::config_file::ConfigFile;
-use crate::config::env_directive::EnvResults;
+use crate::config::env_directive::{EnvDirective, EnvResults};
 use crate::config::tracking::Tracker;
 use crate::file::display_path;
 use crate::shorthands::{get_shorthands, Shorthands};
-use crate::task::Task;
+use crate::task::{
+    import_tasks, is_monorepo_pattern, match_monorepo_tasks, EitherStringOrBool, GetMatchingExt,
+    Task, TaskInclude,
+};
 use crate::toolset::{ToolRequestSet, ToolRequestSetBuilder};
 use crate::ui::style;
 use crate::{backend, dirs, env, file, registry};
@@ -42,6 +48,8 @@ type EnvWithSources = IndexMap<String, (String, PathBuf)>;
 pub struct Config {
     pub aliases: AliasMap,
     pub config_files: ConfigMap,
+    /// config files loaded with `extends` and the config file that extends them
+    pub extended_by: HashMap<PathBuf, PathBuf>,
     pub project_root: Option<PathBuf>,
     env: OnceCell<EnvResults>,
     env_with_sources: OnceCell<EnvWithSources>,
@@ -49,6 +57,7 @@ pub struct Config {
     repo_urls: HashMap<String, String>,
     shorthands: OnceLock<HashMap<String, String>>,
     tasks: OnceCell<BTreeMap<String, Task>>,
+    monorepo_tasks: OnceCell<BTreeMap<String, Task>>,
     tool_request_set: OnceCell<ToolRequestSet>,
 }
 
@@ -82,12 +91,15 @@ impl Config {
         trace!("config_paths: {config_paths:?}");
         let config_files = load_all_config_files(&config_paths, &legacy_files)?;
         time!("load config_files");
+        let (config_files, extended_by) = load_extended_config_files(config_files)?;
+        time!("load extended_config_files");
 
         let config = Self {
             aliases: load_aliases(&config_files)?,
             project_root: get_project_root(&config_files),
             repo_urls: load_plugins(&config_files)?,
             config_files,
+            extended_by,
             ..Default::default()
         };
         time!("load build");
@@ -143,6 +155,10 @@ impl Config {
     pub fn env_results(&self) -> eyre::Result<&EnvResults> {
         self.env.get_or_try_init(|| self.load_env())
     }
+    /// vars that must not be displayed, e.g.: from `env._.secrets`
+    pub fn redactions(&self) -> eyre::Result<&BTreeSet<String>> {
+        Ok(&self.env_results()?.redactions)
+    }
     pub fn path_dirs(&self) -> eyre::Result<&Vec<PathBuf>> {
         Ok(&self.env_results()?.env_paths)
     }
@@ -186,6 +202,37 @@ impl Config {
             .collect())
     }
 
+    /// the root of a monorepo marked with `task_config.monorepo = true`, if there are several
+    /// the one closest to the filesystem root is used
+    pub fn monorepo_root(&self) -> Option<&Path> {
+        self.config_files
+            .values()
+            .filter(|cf| cf.task_config().monorepo == Some(true))
+            .filter_map(|cf| cf.project_root())
+            .next_back()
+    }
+
+    /// tasks of every package in the monorepo named like `//path/to/package:task`
+    pub fn monorepo_tasks(&self) -> Result<&BTreeMap<String, Task>> {
+        self.monorepo_tasks
+            .get_or_try_init(|| self.load_monorepo_tasks())
+    }
+
+    /// tasks matching a pattern given to `mise run` or `depends`, `//` and `...:` patterns match
+    /// tasks of monorepo packages
+    pub fn tasks_matching(&self, pat: &str) -> Result<Vec<&Task>> {
+        if is_monorepo_pattern(pat) {
+            if self.monorepo_root().is_none() {
+                return Err(eyre!(
+                    "{pat} requires a monorepo root, set task_config.monorepo = true in its mise.toml"
+                ));
+            }
+            return match_monorepo_tasks(self.monorepo_tasks()?, pat);
+        }
+        let tasks = self.tasks_with_aliases()?;
+        Ok(tasks.get_matching(pat)?.into_iter().cloned().collect())
+    }
+
     pub fn resolve_alias(&self, backend: &dyn Backend, v: &str) -> Result<String> {
         if let Some(plugin_aliases) = self.aliases.get(backend.fa()) {
             if let Some(alias) = plugin_aliases.get(v) {
@@ -264,27 +311,20 @@ impl Config {
     }
 
     pub fn task_includes_for_dir(&self, dir: &Path) -> Vec<PathBuf> {
-        self.configs_at_root(dir)
-            .iter()
-            .find_map(|cf| cf.task_config().includes.clone())
-            .unwrap_or_else(default_task_includes)
-            .into_par_iter()
-            .map(|p| if p.is_absolute() { p } else { dir.join(p) })
-            .filter(|p| p.exists())
-            .collect::<Vec<_>>()
-            .into_iter()
-            .unique()
-            .collect::<Vec<_>>()
+        task_includes_for_configs(dir, &self.configs_at_root(dir))
     }
 
     pub fn load_tasks_in_dir(&self, dir: &Path) -> Result<Vec<Task>> {
-        let configs = self.configs_at_root(dir);
+        self.load_tasks_in_configs(dir, &self.configs_at_root(dir))
+    }
+
+    fn load_tasks_in_configs(&self, dir: &Path, configs: &[&dyn ConfigFile]) -> Result<Vec<Task>> {
         let config_tasks = configs
             .par_iter()
             .flat_map(|cf| cf.tasks())
             .cloned()
             .collect::<Vec<_>>();
-        let includes = self.task_includes_for_dir(dir);
+        let includes = task_includes_for_configs(dir, configs);
         let extra_tasks = includes
             .par_iter()
             .filter(|p| {
@@ -307,10 +347,24 @@ impl Config {
                 })
             })
             .collect::<Vec<_>>();
+        let imported_tasks = configs
+            .iter()
+            .find_map(|cf| cf.task_config().imports.clone())
+            .unwrap_or_default()
+            .into_par_iter()
+            .map(|p| dir.join(p))
+            .flat_map(|p| {
+                import_tasks(&p).unwrap_or_else(|err| {
+                    warn!("importing tasks from {}: {err:#}", display_path(&p));
+                    vec![]
+                })
+            })
+            .collect::<Vec<_>>();
         Ok(file_tasks
             .into_iter()
             .chain(config_tasks)
             .chain(extra_tasks)
+            .chain(imported_tasks)
             .sorted_by_cached_key(|t| t.name.clone())
             .collect())
     }
@@ -326,8 +380,87 @@ impl Config {
         Ok(tasks.into_values().collect())
     }
 
+    fn load_monorepo_tasks(&self) -> Result<BTreeMap<String, Task>> {
+        let root = match self.monorepo_root() {
+            Some(root) => root,
+            None => return Ok(Default::default()),
+        };
+        time!("load_monorepo_tasks");
+        // skips hidden directories, installed dependencies and anything in .gitignore/.ignore
+        let dirs = WalkBuilder::new(root)
+            .require_git(false)
+            .filter_entry(|e| {
+                e.file_type().is_some_and(|ft| ft.is_dir()) && e.file_name() != "node_modules"
+            })
+            .build()
+            .filter_map(|e| match e {
+                Ok(e) => Some(e.into_path()),
+                Err(err) => {
+                    debug!("skipping monorepo directory: {err}");
+                    None
+                }
+            })
+            .collect_vec();
+        let tasks: BTreeMap<String, Task> = dirs
+            .into_par_iter()
+            .map(|dir| self.load_monorepo_package_tasks(root, &dir))
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .flatten()
+            .map(|t| (t.name.clone(), t))
+            .collect();
+        time!("load_monorepo_tasks {count}", count = tasks.len());
+        Ok(tasks)
+    }
+
+    /// tasks of a monorepo package run in its directory with the tools and env of its config
+    fn load_monorepo_package_tasks(&self, root: &Path, dir: &Path) -> Result<Vec<Task>> {
+        let configs = DEFAULT_CONFIG_FILENAMES
+            .iter()
+            .map(|f| dir.join(f))
+            .filter(|p| p.is_file())
+            .map(|p| config_file::parse(&p))
+            .collect::<Result<Vec<_>>>()?;
+        let configs = configs.iter().map(|cf| cf.as_ref()).collect_vec();
+        let mut tools = BTreeMap::new();
+        let mut env = BTreeMap::new();
+        // apply the configs with the highest precedence last
+        for cf in configs.iter().rev() {
+            for (fa, trs, _) in cf.to_tool_request_set()?.iter() {
+                if !trs.is_empty() {
+                    let versions = trs.iter().map(|tr| tr.version()).join(" ");
+                    tools.insert(fa.to_string(), versions);
+                }
+            }
+            for directive in cf.env_entries()? {
+                match directive {
+                    EnvDirective::Val(k, v) => {
+                        env.insert(k, EitherStringOrBool(Either::Left(v)));
+                    }
+                    EnvDirective::Rm(k) => {
+                        env.insert(k, EitherStringOrBool(Either::Right(false)));
+                    }
+                    directive => {
+                        debug!("{directive} is not supported in monorepo packages, ignoring")
+                    }
+                }
+            }
+        }
+        let package = dir
+            .strip_prefix(root)
+            .unwrap_or(dir)
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .join("/");
+        Ok(self
+            .load_tasks_in_configs(dir, &configs)?
+            .into_iter()
+            .map(|t| t.with_package(&package, dir, &tools, &env))
+            .collect())
+    }
+
     fn load_file_tasks_recursively(&self) -> Result<Vec<Task>> {
-        let file_tasks = file::all_dirs()?
+        let file_tasks = file_task_dirs(self.root_config_dir().as_deref())?
             .into_iter()
             .filter(|d| {
                 if cfg!(test) {
@@ -346,6 +479,17 @@ impl Config {
         Ok(file_tasks)
     }
 
+    /// directory of the `root = true` config file, config files and tasks above it are ignored
+    fn root_config_dir(&self) -> Option<PathBuf> {
+        let cwd = dirs::CWD.as_ref()?;
+        self.config_files
+            .iter()
+            .filter(|(p, cf)| cf.is_root() && !self.extended_by.contains_key(*p))
+            .filter_map(|(p, _)| cwd.ancestors().find(|dir| p.starts_with(dir)))
+            .max_by_key(|dir| dir.components().count())
+            .map(Path::to_path_buf)
+    }
+
     fn load_global_tasks(&self) -> Result<Vec<Task>> {
         let cf = self.config_files.get(&*env::MISE_GLOBAL_CONFIG_FILE);
         Ok(self
@@ -382,7 +526,9 @@ impl Config {
                 .clone()
                 .unwrap_or(vec!["tasks".into()])
                 .into_iter()
-                .map(|p| cf.get_path().parent().unwrap().join(p))
+                .filter_map(|p| {
+                    resolve_task_include(cf.as_ref(), cf.get_path().parent().unwrap(), p)
+                })
                 .collect(),
             None => vec![dirs::CONFIG.join("tasks")],
         };
@@ -617,7 +763,20 @@ pub fn load_config_paths(config_filenames: &[String]) -> Vec<PathBuf> {
     // The current directory is not always available, e.g.
     // when a directory was deleted or inside FUSE mounts.
     if let Some(current_dir) = &*dirs::CWD {
-        config_files.extend(file::FindUp::new(current_dir, config_filenames));
+        let mut root = None;
+        for p in file::FindUp::new(current_dir, config_filenames) {
+            // skip config files in directories above a `root = true` config file
+            if root.as_ref().is_some_and(|root| !p.starts_with(root)) {
+                break;
+            }
+            if root.is_none() && is_root_config(&p) {
+                root = current_dir
+                    .ancestors()
+                    .find(|dir| p.starts_with(dir))
+                    .map(Path::to_path_buf);
+            }
+            config_files.push(p);
+        }
     };
 
     config_files.extend(global_config_files());
@@ -629,6 +788,24 @@ pub fn load_config_paths(config_filenames: &[String]) -> Vec<PathBuf> {
         .collect()
 }
 
+/// the current directory and its parents up to `root`
+fn file_task_dirs(root: Option<&Path>) -> Result<Vec<PathBuf>> {
+    Ok(file::all_dirs()?
+        .into_iter()
+        .take_while(|d| root.map_or(true, |root| d.starts_with(root)))
+        .collect())
+}
+
+/// reads `root = true` from a toml config file before it is parsed as a config file
+fn is_root_config(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "toml")
+        && file::read_to_string(path)
+            .ok()
+            .and_then(|raw| raw.parse::<toml::Table>().ok())
+            .and_then(|t| t.get("root").and_then(|root| root.as_bool()))
+            .unwrap_or_default()
+}
+
 pub fn is_global_config(path: &Path) -> bool {
     global_config_files()
         .iter()
@@ -709,6 +886,77 @@ fn load_all_config_files(
         .collect())
 }
 
+/// loads the config files in `extends` right after the config file that extends them so they
+/// have lower precedence than it but higher precedence than config files in parent directories
+fn load_extended_config_files(
+    config_files: ConfigMap,
+) -> Result<(ConfigMap, HashMap<PathBuf, PathBuf>)> {
+    let mut extended_by = HashMap::new();
+    if config_files.values().all(|cf| cf.extends().is_empty()) {
+        return Ok((config_files, extended_by));
+    }
+    let paths = config_files.keys().cloned().collect::<BTreeSet<_>>();
+    let mut all = ConfigMap::new();
+    for (path, cf) in config_files {
+        let extends = cf.extends().to_vec();
+        all.insert(path.clone(), cf);
+        load_extends(&path, &extends, &paths, &mut all, &mut extended_by)?;
+    }
+    Ok((all, extended_by))
+}
+
+fn load_extends(
+    path: &Path,
+    extends: &[String],
+    paths: &BTreeSet<PathBuf>,
+    all: &mut ConfigMap,
+    extended_by: &mut HashMap<PathBuf, PathBuf>,
+) -> Result<()> {
+    // later entries override earlier ones
+    for source in extends.iter().rev() {
+        let base = resolve_extends(path, source).wrap_err_with(|| {
+            format!(
+                "error loading {source} extended by {}",
+                style::ebold(display_path(path))
+            )
+        })?;
+        // already loaded in its own place or as a base of another config file
+        if paths.contains(&base) || all.contains_key(&base) {
+            continue;
+        }
+        let cf = config_file::parse(&base).wrap_err_with(|| {
+            format!(
+                "error parsing config file: {}",
+                style::ebold(display_path(&base))
+            )
+        })?;
+        if let Err(err) = Tracker::track(&base) {
+            warn!("tracking config: {err:#}");
+        }
+        let base_extends = cf.extends().to_vec();
+        all.insert(base.clone(), cf);
+        extended_by.insert(base.clone(), path.to_path_buf());
+        load_extends(&base, &base_extends, paths, all, extended_by)?;
+    }
+    Ok(())
+}
+
+/// resolves an `extends` entry relative to the config file, remote config files are fetched
+/// into the cache like remote task includes if the config file is trusted
+fn resolve_extends(path: &Path, source: &str) -> Result<PathBuf> {
+    if let Some(include) = TaskInclude::parse(source) {
+        config_file::trust_check(path)?;
+        return include.fetch(false);
+    }
+    let base = file::replace_path(source);
+    let base = match path.parent() {
+        Some(dir) if base.is_relative() => dir.join(base),
+        _ => base,
+    };
+    ensure!(base.is_file(), "{} not found", display_path(&base));
+    Ok(base.absolutize()?.to_path_buf())
+}
+
 fn parse_config_file(
     f: &PathBuf,
     legacy_filenames: &BTreeMap<String, Vec<String>>,
@@ -766,6 +1014,10 @@ impl Debug for Config {
         }
         if let Some(env) = self.env_maybe() {
             if !env.is_empty() {
+                let env = match self.env.get() {
+                    Some(r) => env.iter().map(|(k, v)| (k, r.redact(k, v))).collect(),
+                    None => IndexMap::new(),
+                };
                 s.field("Env", &env);
                 // s.field("Env Sources", &self.env_sources);
             }
@@ -792,6 +1044,46 @@ fn default_task_includes() -> Vec<PathBuf> {
     ]
 }
 
+fn task_includes_for_configs(dir: &Path, configs: &[&dyn ConfigFile]) -> Vec<PathBuf> {
+    let (cf, includes) = configs
+        .iter()
+        .find_map(|cf| cf.task_config().includes.clone().map(|i| (Some(*cf), i)))
+        .unwrap_or_else(|| (None, default_task_includes()));
+    includes
+        .into_par_iter()
+        .filter_map(|p| match cf {
+            Some(cf) => resolve_task_include(cf, dir, p),
+            None if p.is_absolute() => Some(p),
+            None => Some(dir.join(p)),
+        })
+        .filter(|p| p.exists())
+        .collect::<Vec<_>>()
+        .into_iter()
+        .unique()
+        .collect::<Vec<_>>()
+}
+
+/// resolves a `task_config.includes` entry of `cf` to a local path, fetching remote includes
+/// into the cache if the config file is trusted
+fn resolve_task_include(cf: &dyn ConfigFile, dir: &Path, p: PathBuf) -> Option<PathBuf> {
+    let include = match TaskInclude::parse(&p.to_string_lossy()) {
+        Some(include) => include,
+        None if p.is_absolute() => return Some(p),
+        None => return Some(dir.join(p)),
+    };
+    let fetch = || {
+        config_file::trust_check(cf.get_path())?;
+        include.fetch(false)
+    };
+    match fetch() {
+        Ok(p) => Some(p),
+        Err(err) => {
+            warn!("loading tasks from {}: {err:#}", include.source);
+            None
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use insta::assert_debug_snapshot;
@@ -806,4 +1098,18 @@ mod tests {
         let config = Config::load().unwrap();
         assert_debug_snapshot!(config);
     }
+
+    #[test]
+    fn test_file_task_dirs() {
+        reset();
+        let cwd = dirs::CWD.clone().unwrap();
+        let all = file::all_dirs().unwrap();
+        assert_eq!(file_task_dirs(None).unwrap(), all);
+        assert_eq!(file_task_dirs(Some(&cwd)).unwrap(), vec![cwd.clone()]);
+        let parent = cwd.parent().unwrap();
+        assert_eq!(
+            file_task_dirs(Some(parent)).unwrap(),
+            vec![cwd.clone(), parent.to_path_buf()]
+        );
+    }
 }
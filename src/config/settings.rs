@@ -210,6 +210,11 @@ impl Settings {
                 s.raw = Some(true);
             }
         }
+        // only subcommands which define `--locked` (e.g. `mise install`), not task args
+        let mut subcommands = std::iter::successors(Some(m), |m| m.subcommand().map(|(_, m)| m));
+        if subcommands.any(|m| matches!(m.try_get_one::<bool>("locked"), Ok(Some(true)))) {
+            s.locked = Some(true);
+        }
         if let Some(cd) = m.get_one::<PathBuf>("cd") {
             s.cd = Some(cd.clone());
         }
@@ -14,6 +14,8 @@ use log::LevelFilter;
 use once_cell::sync::Lazy;
 
 pub static ARGS: RwLock<Vec<String>> = RwLock::new(vec![]);
+/// the parsed subcommand and its nested subcommands, e.g.: `["tasks", "run"]`
+pub static SUBCOMMANDS: RwLock<Vec<String>> = RwLock::new(vec![]);
 #[cfg(unix)]
 pub static SHELL: Lazy<String> = Lazy::new(|| var("SHELL").unwrap_or_else(|_| "sh".into()));
 #[cfg(windows)]
@@ -3,6 +3,8 @@ use std::ffi::OsString;
 use std::fmt::Debug;
 use std::io::prelude::*;
 use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
 
 use base64::prelude::*;
 use eyre::Result;
@@ -10,11 +12,13 @@ use flate2::write::{ZlibDecoder, ZlibEncoder};
 use flate2::Compression;
 use itertools::Itertools;
 use serde_derive::{Deserialize, Serialize};
+use xx::regex;
 
+use crate::cmd::CmdLineRunner;
 use crate::env::PATH_KEY;
 use crate::{cmd, file};
 
-#[derive(Default, Serialize, Deserialize)]
+#[derive(Clone, Default, Serialize, Deserialize)]
 pub struct EnvDiff {
     #[serde(default)]
     pub old: HashMap<String, String>,
@@ -24,6 +28,30 @@ pub struct EnvDiff {
     pub path: Vec<PathBuf>,
 }
 
+/// shell used to run an `env._.source` script
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, strum::Display)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum SourceShell {
+    Bash,
+    Sh,
+    Zsh,
+    Fish,
+    Nu,
+}
+
+impl SourceShell {
+    /// guesses the shell from the extension of the script, defaults to bash
+    pub fn from_script(script: &Path) -> Self {
+        match script.extension().and_then(|e| e.to_str()) {
+            Some("fish") => Self::Fish,
+            Some("nu") => Self::Nu,
+            Some("zsh") => Self::Zsh,
+            _ => Self::Bash,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum EnvDiffOperation {
     Add(String, String),
@@ -88,7 +116,7 @@ impl EnvDiff {
             match line.strip_prefix("declare -x ") {
                 Some(line) => {
                     let (k, v) = line.split_once('=').unwrap_or_default();
-                    if valid_key(k) {
+                    if is_ignored_key(k) {
                         continue;
                     }
                     cur_key = Some(k.to_string());
@@ -115,6 +143,60 @@ impl EnvDiff {
         Ok(Self::new(&env, additions))
     }
 
+    /// sources `script` with `shell` and diffs the env it ends up with against `env`, unlike
+    /// `from_bash_script` this also finds vars that were removed
+    pub fn from_script<T, U, V>(
+        script: &Path,
+        shell: SourceShell,
+        env: T,
+        timeout: Option<Duration>,
+    ) -> Result<Self>
+    where
+        T: IntoIterator<Item = (U, V)>,
+        U: Into<String>,
+        V: Into<String>,
+    {
+        const MARKER: &str = "__MISE_SOURCE_ENV__";
+        let env: HashMap<String, String> =
+            env.into_iter().map(|(k, v)| (k.into(), v.into())).collect();
+        let path = shell_escape::escape(script.to_string_lossy());
+        let script = match shell {
+            SourceShell::Bash | SourceShell::Sh | SourceShell::Zsh => {
+                format!(". {path}\necho {MARKER}\n{PRINT_ENV}")
+            }
+            SourceShell::Fish => format!("source {path}\necho {MARKER}\n{PRINT_ENV}"),
+            // nushell builtins do not write to stdout so the externals are called instead
+            SourceShell::Nu => format!("source {path}\n^echo {MARKER}\n^{PRINT_ENV}"),
+        };
+        let stdout = Mutex::new(vec![]);
+        CmdLineRunner::new(shell.to_string())
+            .arg("-c")
+            .arg(script)
+            .env_clear()
+            .envs(&env)
+            .with_on_stdout(|line| stdout.lock().unwrap().push(line))
+            // the env of the script must not be printed with --raw
+            .with_capture()
+            .with_timeout(timeout.unwrap_or(DEFAULT_SOURCE_TIMEOUT))
+            .execute()?;
+        let stdout = stdout.into_inner().unwrap();
+        let new_env: HashMap<String, String> = stdout
+            .iter()
+            .skip_while(|line| *line != MARKER)
+            .skip(1)
+            .filter_map(|kv| kv.split_once('='))
+            .filter(|(k, _)| !is_ignored_key(k))
+            .map(|(k, v)| (k.to_string(), unescape_env_value(v)))
+            .collect();
+        let mut diff = Self::new(&env, new_env.clone());
+        for (k, v) in env {
+            if !is_ignored_key(&k) && !new_env.contains_key(&k) {
+                diff.old.insert(k, v);
+            }
+        }
+        Ok(diff)
+    }
+
     pub fn deserialize(raw: &str) -> Result<EnvDiff> {
         let mut writer = Vec::new();
         let mut decoder = ZlibDecoder::new(writer);
@@ -157,7 +239,25 @@ impl EnvDiff {
     }
 }
 
-fn valid_key(k: &str) -> bool {
+/// how long a script sourced by `from_script` may run if it has no `timeout`
+const DEFAULT_SOURCE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// prints the env one var per line with POSIX awk, which every shell can run unlike `env -0`.
+/// `%`, newlines and carriage returns in values are percent-encoded.
+const PRINT_ENV: &str = r#"awk 'BEGIN { for (k in ENVIRON) { v = ENVIRON[k]; gsub(/%/, "%25", v); gsub(/\n/, "%0A", v); gsub(/\r/, "%0D", v); print k "=" v } }'"#;
+
+fn unescape_env_value(v: &str) -> String {
+    regex!(r"%(25|0A|0D)")
+        .replace_all(v, |c: &regex::Captures| match &c[1] {
+            "0A" => "\n",
+            "0D" => "\r",
+            _ => "%",
+        })
+        .to_string()
+}
+
+/// true for vars that are ignored when diffing the env of a script
+pub fn is_ignored_key(k: &str) -> bool {
     k.is_empty()
         || k == "_"
         || k == "SHLVL"
@@ -356,6 +456,55 @@ mod tests {
         assert_debug_snapshot!(ed);
     }
 
+    #[test]
+    fn test_from_script() {
+        reset();
+        let script = dirs::HOME.join("fixtures/source-env.sh");
+        file::write(
+            &script,
+            "export ADDED='a b'\nexport MODIFIED=2\nexport MULTILINE='1\n2'\nexport PERCENT='100%0A'\nunset REMOVED\n",
+        )
+        .unwrap();
+        let orig = [
+            ("MODIFIED", "1"),
+            ("REMOVED", "1"),
+            ("PATH", "/usr/bin:/bin"),
+        ];
+        let ed = EnvDiff::from_script(&script, SourceShell::Sh, orig, None).unwrap();
+        file::remove_file(&script).unwrap();
+        assert_debug_snapshot!(ed, @r###"
+        EnvDiff {
+            old: [
+                "MODIFIED=1",
+                "REMOVED=1",
+            ],
+            new: [
+                "ADDED=a b",
+                "MODIFIED=2",
+                "MULTILINE=1\n2",
+                "PERCENT=100%0A",
+            ],
+        }
+        "###);
+    }
+
+    #[test]
+    fn test_source_shell() {
+        assert_eq!(
+            SourceShell::from_script(Path::new("env.fish")),
+            SourceShell::Fish
+        );
+        assert_eq!(
+            SourceShell::from_script(Path::new("env.nu")),
+            SourceShell::Nu
+        );
+        assert_eq!(
+            SourceShell::from_script(Path::new("env.sh")),
+            SourceShell::Bash
+        );
+        assert_eq!(SourceShell::Zsh.to_string(), "zsh");
+    }
+
     #[test]
     fn test_invalid_escape_sequence() {
         reset();
@@ -2,6 +2,7 @@ use std::collections::HashMap;
 use std::fmt::Display;
 use std::fs;
 use std::fs::File;
+use std::io::Read;
 #[cfg(unix)]
 use std::os::unix::fs::symlink;
 #[cfg(unix)]
@@ -10,6 +11,7 @@ use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 use std::time::Duration;
 
+use bzip2::read::BzDecoder;
 use color_eyre::eyre::{Context, Result};
 use filetime::{set_file_times, FileTime};
 use flate2::read::GzDecoder;
@@ -18,6 +20,7 @@ use once_cell::sync::Lazy;
 use rayon::prelude::*;
 use tar::Archive;
 use walkdir::WalkDir;
+use xz2::read::XzDecoder;
 use zip::ZipArchive;
 
 use crate::{dirs, env};
@@ -506,6 +509,55 @@ pub fn unzip(archive: &Path, dest: &Path) -> Result<()> {
         .wrap_err_with(|| format!("failed to extract zip archive: {}", display_path(archive)))
 }
 
+/// `.zip`, `.tgz` or `.tar.*` archives that `extract` can extract
+pub fn is_archive(filename: &str) -> bool {
+    filename.ends_with(".zip") || filename.contains(".tar") || filename.ends_with(".tgz")
+}
+
+/// extracts `.zip` archives and tarballs compressed with gz, xz, bz2 or zstd based on the extension
+pub fn extract(archive: &Path, dest: &Path) -> Result<()> {
+    let filename = archive.file_name().unwrap_or_default().to_string_lossy();
+    if filename.ends_with(".zip") {
+        return unzip(archive, dest);
+    }
+    let f = File::open(archive)?;
+    let tar: Box<dyn Read> = if filename.ends_with(".gz") || filename.ends_with(".tgz") {
+        Box::new(GzDecoder::new(f))
+    } else if filename.ends_with(".xz") {
+        Box::new(XzDecoder::new(f))
+    } else if filename.ends_with(".bz2") {
+        Box::new(BzDecoder::new(f))
+    } else if filename.ends_with(".zst") {
+        Box::new(zstd::Decoder::new(f)?)
+    } else {
+        Box::new(f)
+    };
+    Archive::new(tar).unpack(dest).wrap_err_with(|| {
+        let archive = display_path(archive);
+        let dest = display_path(dest);
+        format!("failed to extract tar: {archive} to {dest}")
+    })
+}
+
+/// moves the extracted files into `dest` without the first `strip_components` directories
+pub fn move_stripped(src: &Path, dest: &Path, strip_components: usize) -> Result<()> {
+    for entry in WalkDir::new(src).min_depth(strip_components + 1) {
+        let entry = entry?;
+        let path = entry.path();
+        let rel = path.strip_prefix(src)?.components().skip(strip_components);
+        let target = dest.join(rel.collect::<PathBuf>());
+        if entry.file_type().is_dir() {
+            create_dir_all(&target)?;
+        } else {
+            if let Some(parent) = target.parent() {
+                create_dir_all(parent)?;
+            }
+            rename(path, &target)?;
+        }
+    }
+    Ok(())
+}
+
 #[cfg(windows)]
 pub fn un7z(archive: &Path, dest: &Path) -> Result<()> {
     sevenz_rust::decompress_file(archive, dest)
@@ -597,4 +649,13 @@ mod tests {
         assert_eq!(replace_path(Path::new("~/cwd")), dirs::HOME.join("cwd"));
         assert_eq!(replace_path(Path::new("/cwd")), Path::new("/cwd"));
     }
+
+    #[test]
+    fn test_is_archive() {
+        assert!(is_archive("tool.tar.gz"));
+        assert!(is_archive("tool.tar.xz"));
+        assert!(is_archive("tool.tgz"));
+        assert!(is_archive("tool.zip"));
+        assert!(!is_archive("tool-linux-amd64"));
+    }
 }
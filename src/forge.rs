@@ -0,0 +1,785 @@
+use std::fmt::{Display, Formatter};
+use std::process::Command;
+
+use eyre::{bail, eyre, Result};
+use heck::ToShoutySnakeCase;
+use itertools::Itertools;
+use once_cell::sync::Lazy;
+use reqwest::header::HeaderMap;
+use serde::de::DeserializeOwned;
+use serde_derive::Deserialize;
+use url::{form_urlencoded, Url};
+use xx::regex;
+
+use crate::config::SETTINGS;
+use crate::github::GithubRelease;
+use crate::http::HTTP_FETCH;
+use crate::toolset::ToolVersionOptions;
+use crate::{env, file, github};
+
+/// a project on GitHub Enterprise Server, GitLab or a Gitea/Forgejo instance
+///
+/// - `gitlab:group/project` or `gitlab:gitlab.example.com/group/subgroup/project`
+/// - `gitea:codeberg.org/owner/repo`
+/// - `gitlab.example.com/group/project` with the `forge = "gitlab"` tool option
+/// - `owner/repo` with the `api_url` tool option or `github_api_url` setting
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Forge {
+    pub forge_type: ForgeType,
+    pub host: String,
+    pub project: String,
+    api_url: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, strum::EnumString, strum::Display)]
+#[strum(serialize_all = "snake_case")]
+pub enum ForgeType {
+    Github,
+    Gitlab,
+    Gitea,
+}
+
+#[derive(Debug, Clone)]
+pub struct ForgeRelease {
+    pub tag_name: String,
+    pub name: Option<String>,
+    pub prerelease: bool,
+    pub draft: bool,
+    pub published_at: Option<String>,
+    pub assets: Vec<ForgeAsset>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForgeAsset {
+    pub name: String,
+    pub url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GitlabRelease {
+    tag_name: String,
+    name: Option<String>,
+    #[serde(default)]
+    upcoming_release: bool,
+    released_at: Option<String>,
+    assets: GitlabAssets,
+}
+
+#[derive(Debug, Deserialize)]
+struct GitlabAssets {
+    links: Vec<GitlabLink>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GitlabLink {
+    name: String,
+    url: String,
+    direct_asset_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GiteaRelease {
+    tag_name: String,
+    name: Option<String>,
+    #[serde(default)]
+    prerelease: bool,
+    #[serde(default)]
+    draft: bool,
+    published_at: Option<String>,
+    assets: Vec<GiteaAsset>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GiteaAsset {
+    name: String,
+    browser_download_url: String,
+}
+
+impl Forge {
+    /// returns None for projects on github.com which ubi installs itself
+    pub fn parse(name: &str, opts: &ToolVersionOptions) -> Result<Option<Self>> {
+        let (forge_type, name) = match name.split_once(':') {
+            Some((prefix, rest)) if !rest.starts_with("//") => match prefix.parse::<ForgeType>() {
+                Ok(forge_type) => (forge_type, rest),
+                Err(_) => bail!("unknown forge: {prefix}, expected github, gitlab or gitea"),
+            },
+            _ => {
+                let forge = opts.get("forge").map(|f| f.as_str()).unwrap_or("github");
+                match forge.parse::<ForgeType>() {
+                    Ok(forge_type) => (forge_type, name),
+                    Err(_) => bail!("unknown forge: {forge}, expected github, gitlab or gitea"),
+                }
+            }
+        };
+        let api_url = opts.get("api_url").map(|u| u.trim_end_matches('/'));
+        let (host, project, api_url) = match forge_type {
+            ForgeType::Github => {
+                let api_url = api_url.unwrap_or(github::api_url());
+                if api_url == github::DEFAULT_API_URL {
+                    return Ok(None);
+                }
+                let host = Url::parse(api_url)?
+                    .host_str()
+                    .ok_or_else(|| eyre!("invalid api_url: {api_url}"))?
+                    .to_string();
+                (host, name, api_url.to_string())
+            }
+            _ => {
+                // a host has a dot in it unlike a GitLab group or Gitea owner
+                let (host, project) = match name.split_once('/') {
+                    Some((host, project)) if host.contains('.') => (host, project),
+                    _ => (forge_type.default_host(), name),
+                };
+                let api_url = match api_url {
+                    Some(api_url) => api_url.to_string(),
+                    None => forge_type.default_api_url(host),
+                };
+                (host.to_string(), project, api_url)
+            }
+        };
+        if !project.contains('/') {
+            bail!("invalid {forge_type} project: {name}, expected owner/repo");
+        }
+        Ok(Some(Self {
+            forge_type,
+            host,
+            project: project.trim_end_matches('/').to_string(),
+            api_url,
+        }))
+    }
+
+    /// the project name without the owner or groups, used as the default executable name
+    pub fn repo(&self) -> &str {
+        self.project.rsplit('/').next().unwrap_or(&self.project)
+    }
+
+    pub fn list_releases(&self) -> Result<Vec<ForgeRelease>> {
+        match self.forge_type {
+            ForgeType::Github => {
+                let releases = github::list_releases_from_url(&self.api_url, &self.project)?;
+                Ok(releases.into_iter().map(|r| r.into()).collect())
+            }
+            ForgeType::Gitlab => {
+                let url = format!("{}/releases?per_page=100", self.project_url());
+                let releases: Vec<GitlabRelease> = list_pages(url)?;
+                Ok(releases.into_iter().map(|r| r.into()).collect())
+            }
+            ForgeType::Gitea => {
+                let url = format!("{}/releases?limit=50", self.project_url());
+                let releases: Vec<GiteaRelease> = list_pages(url)?;
+                Ok(releases.into_iter().map(|r| r.into()).collect())
+            }
+        }
+    }
+
+    pub fn get_release(&self, tag: &str) -> Result<ForgeRelease> {
+        let tag = form_urlencoded::byte_serialize(tag.as_bytes()).collect::<String>();
+        match self.forge_type {
+            ForgeType::Github => {
+                Ok(github::get_release_for_url(&self.api_url, &self.project, &tag)?.into())
+            }
+            ForgeType::Gitlab => {
+                let url = format!("{}/releases/{tag}", self.project_url());
+                Ok(HTTP_FETCH.json::<GitlabRelease, _>(url)?.into())
+            }
+            ForgeType::Gitea => {
+                let url = format!("{}/releases/tags/{tag}", self.project_url());
+                Ok(HTTP_FETCH.json::<GiteaRelease, _>(url)?.into())
+            }
+        }
+    }
+
+    fn project_url(&self) -> String {
+        match self.forge_type {
+            ForgeType::Gitlab => {
+                let id =
+                    form_urlencoded::byte_serialize(self.project.as_bytes()).collect::<String>();
+                format!("{}/projects/{id}", self.api_url)
+            }
+            ForgeType::Github | ForgeType::Gitea => {
+                format!("{}/repos/{}", self.api_url, self.project)
+            }
+        }
+    }
+}
+
+/// fetches the first page of `url`, or every page with MISE_LIST_ALL_VERSIONS like github releases
+fn list_pages<T: DeserializeOwned>(url: String) -> Result<Vec<T>> {
+    let (mut items, mut headers) = HTTP_FETCH.json_headers::<Vec<T>, _>(&url)?;
+    if *env::MISE_LIST_ALL_VERSIONS {
+        while let Some(next) = next_page(&url, &headers) {
+            let (more, h) = HTTP_FETCH.json_headers::<Vec<T>, _>(next)?;
+            items.extend(more);
+            headers = h;
+        }
+    }
+    Ok(items)
+}
+
+/// GitLab and Gitea send a `Link` header like GitHub, GitLab also sends `X-Next-Page`
+fn next_page(url: &str, headers: &HeaderMap) -> Option<String> {
+    github::next_page(headers).or_else(|| {
+        let page = headers.get("x-next-page")?.to_str().ok()?;
+        (!page.is_empty()).then(|| format!("{url}&page={page}"))
+    })
+}
+
+impl Display for Forge {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}/{}", self.forge_type, self.host, self.project)
+    }
+}
+
+impl ForgeType {
+    fn default_host(&self) -> &'static str {
+        match self {
+            ForgeType::Github => "github.com",
+            ForgeType::Gitlab => "gitlab.com",
+            ForgeType::Gitea => "gitea.com",
+        }
+    }
+
+    fn default_api_url(&self, host: &str) -> String {
+        match self {
+            ForgeType::Github => github::api_url().to_string(),
+            ForgeType::Gitlab => format!("https://{host}/api/v4"),
+            ForgeType::Gitea => format!("https://{host}/api/v1"),
+        }
+    }
+}
+
+impl From<GithubRelease> for ForgeRelease {
+    fn from(r: GithubRelease) -> Self {
+        Self {
+            tag_name: r.tag_name,
+            name: r.name,
+            prerelease: r.prerelease,
+            draft: r.draft,
+            published_at: r.published_at,
+            assets: r
+                .assets
+                .into_iter()
+                .map(|a| ForgeAsset {
+                    name: a.name,
+                    url: a.browser_download_url,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<GitlabRelease> for ForgeRelease {
+    fn from(r: GitlabRelease) -> Self {
+        Self {
+            tag_name: r.tag_name,
+            name: r.name,
+            prerelease: r.upcoming_release,
+            draft: false,
+            published_at: r.released_at,
+            assets: r
+                .assets
+                .links
+                .into_iter()
+                .map(|l| ForgeAsset {
+                    name: l.name,
+                    url: l.direct_asset_url.unwrap_or(l.url),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<GiteaRelease> for ForgeRelease {
+    fn from(r: GiteaRelease) -> Self {
+        Self {
+            tag_name: r.tag_name,
+            name: r.name,
+            prerelease: r.prerelease,
+            draft: r.draft,
+            published_at: r.published_at,
+            assets: r
+                .assets
+                .into_iter()
+                .map(|a| ForgeAsset {
+                    name: a.name,
+                    url: a.browser_download_url,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl ForgeRelease {
+    /// drafts are never listed, prereleases only with the `prerelease = true` tool option
+    pub fn is_listed(&self, prerelease: bool) -> bool {
+        !self.draft && (prerelease || !self.prerelease)
+    }
+
+    /// the asset for the current os/arch, picked the same way ubi picks release assets
+    pub fn find_asset(&self, matching: Option<&str>) -> Option<&ForgeAsset> {
+        pick_asset(
+            &self.assets,
+            matching,
+            env::consts::OS,
+            env::consts::ARCH,
+            *IS_MUSL,
+        )
+    }
+}
+
+/// linux distros like alpine that link against musl instead of glibc, detected like ubi does
+static IS_MUSL: Lazy<bool> = Lazy::new(|| {
+    cfg!(target_os = "linux")
+        && Command::new("ldd")
+            .arg(file::which("ls").unwrap_or_else(|| "/bin/ls".into()))
+            .output()
+            .is_ok_and(|o| {
+                o.status.success() && String::from_utf8_lossy(&o.stdout).contains("musl")
+            })
+});
+
+/// mirrors ubi's asset picker: skip files that aren't binaries or archives, take the only one
+/// left or match os then arch (falling back to assets without an arch), drop glibc builds on
+/// musl, then narrow down by `matching`, 64-bit, apple silicon and libc before picking the first
+/// by name
+fn pick_asset<'a>(
+    assets: &'a [ForgeAsset],
+    matching: Option<&str>,
+    os: &str,
+    arch: &str,
+    musl: bool,
+) -> Option<&'a ForgeAsset> {
+    let assets = assets
+        .iter()
+        .filter(|a| has_binary_extension(&a.name))
+        .collect_vec();
+    if let [asset] = assets[..] {
+        return Some(asset);
+    }
+    let os_re = match os {
+        "linux" => regex!(r"(?i:(?:\b|_)linux(?:\b|_|32|64))"),
+        "macos" => regex!(r"(?i:(?:\b|_)(?:darwin|macos|osx)(?:\b|_))"),
+        "windows" => regex!(r"(?i:(?:\b|_)win(?:32|64|dows)?(?:\b|_))"),
+        "freebsd" => regex!(r"(?i:(?:\b|_)freebsd(?:\b|_))"),
+        "netbsd" => regex!(r"(?i:(?:\b|_)netbsd(?:\b|_))"),
+        _ => regex!(r"(?i:(?:\b|_)openbsd(?:\b|_))"),
+    };
+    let macos_arm = os == "macos" && arch == "aarch64";
+    let arch_re = match arch {
+        // apple silicon can run x86_64 binaries with rosetta
+        "aarch64" if macos_arm => {
+            regex!(r"(?i:(?:\b|_)(?:aarch_?64|arm_?64|x86[_-]64|x64|amd64)(?:\b|_))")
+        }
+        "aarch64" => regex!(r"(?i:(?:\b|_)(?:aarch_?64|arm_?64)(?:\b|_))"),
+        "x86_64" => regex!(
+            r"(?i:(?:\b|_)(?:386|i586|i686|x86[_-]32|x86[_-]64|x64|amd64|linux64|win64)(?:\b|_))"
+        ),
+        "x86" => regex!(r"(?i:(?:\b|_)(?:386|i586|i686|x86[_-]32|win32)(?:\b|_))"),
+        _ => regex!(r"(?i:(?:\b|_)arm(?:v[0-7])?(?:\b|_))"),
+    };
+    let any_arch_re = regex!(
+        r"(?i:(?:\b|_)(?:aarch_?64|arm_?64|arm(?:v[0-7])?|mips\w*|ppc\w*|powerpc\w*|riscv\w*|s390x|sparc\w*|386|i586|i686|x86[_-](?:32|64)|x64|amd64)(?:\b|_))"
+    );
+    let os_matches = assets
+        .into_iter()
+        .filter(|a| os_re.is_match(&a.name))
+        .collect_vec();
+    let mut matches = os_matches
+        .iter()
+        .filter(|a| arch_re.is_match(&a.name))
+        .copied()
+        .collect_vec();
+    if matches.is_empty() {
+        matches = os_matches
+            .into_iter()
+            .filter(|a| !any_arch_re.is_match(&a.name))
+            .collect();
+    }
+    if musl {
+        matches.retain(|a| !a.name.contains("-gnu") && !a.name.contains("-glibc"));
+    }
+    if let Some(m) = matching {
+        return matches.into_iter().find(|a| a.name.contains(m));
+    }
+    let prefer = |matches: Vec<&'a ForgeAsset>, f: &dyn Fn(&str) -> bool| {
+        if matches.len() > 1 && matches.iter().any(|a| f(&a.name)) {
+            matches.into_iter().filter(|a| f(&a.name)).collect()
+        } else {
+            matches
+        }
+    };
+    if arch.ends_with("64") {
+        matches = prefer(matches, &|n| n.contains("64"));
+    }
+    if macos_arm {
+        matches = prefer(matches, &|n| {
+            regex!(r"(?i:(?:\b|_)(?:aarch_?64|arm_?64)(?:\b|_))").is_match(n)
+        });
+    }
+    matches = prefer(matches, &|n| n.contains("musl") == musl);
+    matches.into_iter().min_by_key(|a| &a.name)
+}
+
+/// executables, archives and compressed executables, but not packages like .deb or .rpm,
+/// checksums or signatures
+fn has_binary_extension(name: &str) -> bool {
+    const EXTENSIONS: &[&str] = &[
+        ".bz", ".bz2", ".exe", ".gz", ".tar", ".tbz", ".tgz", ".txz", ".xz", ".zip",
+    ];
+    if EXTENSIONS.iter().any(|ext| name.ends_with(ext)) {
+        return true;
+    }
+    let Some((_, ext)) = name.rsplit_once('.') else {
+        return true;
+    };
+    // e.g.: "tool_1.2.3_linux_amd64" or "tool.linux"
+    let version_ext = regex!(r"\d+\.(\d+[^.]+)$")
+        .captures(name)
+        .is_some_and(|c| &c[1] == ext);
+    version_ext
+        || regex!(
+            r"(?i:^(?:linux|darwin|macos|osx|win(?:32|64|dows)?|freebsd|netbsd|openbsd)(?:\b|_))"
+        )
+        .is_match(ext)
+}
+
+/// `MISE_<HOST>_TOKEN`, e.g.: `MISE_GITLAB_EXAMPLE_COM_TOKEN` for gitlab.example.com.
+/// `GITLAB_TOKEN` and `GITEA_TOKEN` are only sent to gitlab.com and gitea.com and
+/// `GITHUB_ENTERPRISE_TOKEN` to the host of `github_api_url`.
+pub fn token(host: &str) -> Option<String> {
+    let var = format!("MISE_{}_TOKEN", host.to_shouty_snake_case());
+    env::var(&var).ok().or_else(|| match host {
+        "gitlab.com" => env::var("GITLAB_TOKEN").ok(),
+        "gitea.com" => env::var("GITEA_TOKEN").ok(),
+        _ if Url::parse(&SETTINGS.github_api_url)
+            .is_ok_and(|u| u.host_str() == Some(host) && host != "api.github.com") =>
+        {
+            env::var("GITHUB_ENTERPRISE_TOKEN").ok()
+        }
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let opts = ToolVersionOptions::new();
+        assert_eq!(Forge::parse("goreleaser/goreleaser", &opts).unwrap(), None);
+        let forge = Forge::parse("gitlab:gitlab-org/cli", &opts)
+            .unwrap()
+            .unwrap();
+        assert_eq!(forge.forge_type, ForgeType::Gitlab);
+        assert_eq!(forge.host, "gitlab.com");
+        assert_eq!(forge.project, "gitlab-org/cli");
+        assert_eq!(forge.repo(), "cli");
+        assert_eq!(
+            forge.project_url(),
+            "https://gitlab.com/api/v4/projects/gitlab-org%2Fcli"
+        );
+        let forge = Forge::parse("gitea:codeberg.org/owner/repo", &opts)
+            .unwrap()
+            .unwrap();
+        assert_eq!(forge.host, "codeberg.org");
+        assert_eq!(
+            forge.project_url(),
+            "https://codeberg.org/api/v1/repos/owner/repo"
+        );
+        let opts = [("forge".to_string(), "gitlab".to_string())].into();
+        let forge = Forge::parse("git.example.com/group/sub/tool", &opts)
+            .unwrap()
+            .unwrap();
+        assert_eq!(forge.host, "git.example.com");
+        assert_eq!(forge.project, "group/sub/tool");
+        let opts = [(
+            "api_url".to_string(),
+            "https://github.example.com/api/v3/".to_string(),
+        )]
+        .into();
+        let forge = Forge::parse("owner/tool", &opts).unwrap().unwrap();
+        assert_eq!(forge.forge_type, ForgeType::Github);
+        assert_eq!(forge.host, "github.example.com");
+        assert_eq!(
+            forge.project_url(),
+            "https://github.example.com/api/v3/repos/owner/tool"
+        );
+        assert!(Forge::parse("bitbucket:owner/repo", &Default::default()).is_err());
+    }
+
+    #[test]
+    fn test_find_asset() {
+        let asset = |name: &str| ForgeAsset {
+            name: name.to_string(),
+            url: format!("https://example.com/{name}"),
+        };
+        let release = ForgeRelease {
+            tag_name: "v1.0.0".into(),
+            name: None,
+            prerelease: false,
+            draft: false,
+            published_at: None,
+            assets: vec![
+                asset("tool_1.0.0_checksums.txt"),
+                asset("tool_1.0.0_linux_amd64.tar.gz"),
+                asset("tool_1.0.0_linux_amd64.tar.gz.sha256"),
+                asset("tool_1.0.0_linux_arm64.tar.gz"),
+                asset("tool_1.0.0_darwin_amd64.tar.gz"),
+                asset("tool_1.0.0_darwin_arm64.tar.gz"),
+                asset("tool_1.0.0_windows_amd64.zip"),
+            ],
+        };
+        let expected = match (env::consts::OS, env::consts::ARCH) {
+            ("linux", "x86_64") => "tool_1.0.0_linux_amd64.tar.gz",
+            ("linux", "aarch64") => "tool_1.0.0_linux_arm64.tar.gz",
+            ("macos", "x86_64") => "tool_1.0.0_darwin_amd64.tar.gz",
+            ("macos", "aarch64") => "tool_1.0.0_darwin_arm64.tar.gz",
+            ("windows", "x86_64") => "tool_1.0.0_windows_amd64.zip",
+            _ => return,
+        };
+        assert_eq!(release.find_asset(None).unwrap().name, expected);
+    }
+
+    #[test]
+    fn test_pick_asset() {
+        let assets = [
+            "tool_1.0.0_checksums.txt",
+            "tool_1.0.0_linux_amd64.deb",
+            "tool_1.0.0_linux_amd64.rpm",
+            "tool_1.0.0_linux_amd64.apk",
+            "tool_1.0.0_linux_amd64.tar.gz.sha256",
+            "tool-1.0.0-x86_64-unknown-linux-musl.tar.gz",
+            "tool-1.0.0-x86_64-unknown-linux-gnu.tar.gz",
+            "tool-1.0.0-i686-unknown-linux-gnu.tar.gz",
+            "tool-1.0.0-aarch64-unknown-linux-gnu.tar.gz",
+            "tool-1.0.0-x86_64-apple-darwin.tar.gz",
+            "tool-1.0.0-aarch64-apple-darwin.tar.gz",
+            "tool-1.0.0-x86_64-pc-windows-msvc.zip",
+        ]
+        .map(|name| ForgeAsset {
+            name: name.to_string(),
+            url: format!("https://example.com/{name}"),
+        });
+        let pick = |matching, os, arch, musl| {
+            pick_asset(&assets, matching, os, arch, musl).map(|a| a.name.as_str())
+        };
+        assert_eq!(
+            pick(None, "linux", "x86_64", false),
+            Some("tool-1.0.0-x86_64-unknown-linux-gnu.tar.gz")
+        );
+        assert_eq!(
+            pick(None, "linux", "x86_64", true),
+            Some("tool-1.0.0-x86_64-unknown-linux-musl.tar.gz")
+        );
+        assert_eq!(
+            pick(None, "linux", "aarch64", true),
+            None,
+            "glibc builds don't run on musl"
+        );
+        assert_eq!(
+            pick(Some("musl"), "linux", "x86_64", false),
+            Some("tool-1.0.0-x86_64-unknown-linux-musl.tar.gz")
+        );
+        assert_eq!(pick(Some("static"), "linux", "aarch64", false), None);
+        assert_eq!(
+            pick(None, "macos", "aarch64", false),
+            Some("tool-1.0.0-aarch64-apple-darwin.tar.gz")
+        );
+        assert_eq!(
+            pick(None, "windows", "x86_64", false),
+            Some("tool-1.0.0-x86_64-pc-windows-msvc.zip")
+        );
+
+        let assets = [ForgeAsset {
+            name: "tool_1.0.0_linux_amd64.deb".into(),
+            url: "https://example.com/tool_1.0.0_linux_amd64.deb".into(),
+        }];
+        assert_eq!(pick_asset(&assets, None, "linux", "x86_64", false), None);
+        assert!(has_binary_extension("tool_3.2.1_linux_amd64"));
+        assert!(has_binary_extension("tool"));
+        assert!(!has_binary_extension("tool_1.0.0_linux_amd64.tar.gz.sig"));
+    }
+
+    /// serves a github.com style release of `assets` on localhost, each asset contains an executable
+    /// named "tool" whose content is the name of the asset so the installed binary shows which
+    /// asset was picked
+    #[cfg(unix)]
+    fn serve_release(assets: &[&str]) -> String {
+        use std::io::{BufRead, BufReader, Write};
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let release = serde_json::json!({
+            "assets": assets
+                .iter()
+                .map(|name| serde_json::json!({
+                    "name": name,
+                    "url": format!("{base}/download/{name}"),
+                }))
+                .collect_vec(),
+        })
+        .to_string();
+        let asset = |name: &str| -> Vec<u8> {
+            if !name.ends_with(".tar.gz") {
+                return name.as_bytes().to_vec();
+            }
+            let mut header = tar::Header::new_gnu();
+            header.set_size(name.len() as u64);
+            header.set_mode(0o755);
+            let mut tar = tar::Builder::new(flate2::write::GzEncoder::new(
+                vec![],
+                flate2::Compression::fast(),
+            ));
+            tar.append_data(&mut header, "tool", name.as_bytes())
+                .unwrap();
+            tar.into_inner().unwrap().finish().unwrap()
+        };
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(&stream);
+                let mut request = String::new();
+                reader.read_line(&mut request).unwrap();
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap() > 2 {
+                    line.clear();
+                }
+                let path = request.split_whitespace().nth(1).unwrap_or_default();
+                let body = match path.strip_prefix("/download/") {
+                    Some(name) => asset(name),
+                    None => release.clone().into_bytes(),
+                };
+                write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    body.len()
+                )
+                .unwrap();
+                stream.write_all(&body).unwrap();
+            }
+        });
+        base
+    }
+
+    /// releases on other forges are installed with `find_asset`, it should pick the asset ubi
+    /// picks for the same release on github.com
+    #[cfg(unix)]
+    #[test]
+    fn test_find_asset_like_ubi() {
+        let ubi_pick = |base: &str, matching: Option<&str>| {
+            let dir = tempfile::tempdir().unwrap();
+            let mut builder = ubi::UbiBuilder::new()
+                .project("owner/tool")
+                .tag("v1.0.0")
+                .install_dir(dir.path().to_path_buf())
+                .github_api_url_base(base.to_string());
+            if let Some(matching) = matching {
+                builder = builder.matching(matching);
+            }
+            let rt = tokio::runtime::Builder::new_current_thread()
+                .enable_io()
+                .enable_time()
+                .build()
+                .unwrap();
+            rt.block_on(builder.build().unwrap().install_binary())
+                .unwrap();
+            file::read_to_string(dir.path().join("tool")).unwrap()
+        };
+        let releases: [&[&str]; 3] = [
+            &[
+                "tool_1.0.0_checksums.txt",
+                "tool_1.0.0_linux_amd64.deb",
+                "tool_1.0.0_linux_amd64.rpm",
+                "tool-1.0.0-x86_64-unknown-linux-musl.tar.gz",
+                "tool-1.0.0-x86_64-unknown-linux-gnu.tar.gz",
+                "tool-1.0.0-i686-unknown-linux-gnu.tar.gz",
+                "tool-1.0.0-aarch64-unknown-linux-musl.tar.gz",
+                "tool-1.0.0-aarch64-unknown-linux-gnu.tar.gz",
+                "tool-1.0.0-x86_64-apple-darwin.tar.gz",
+                "tool-1.0.0-aarch64-apple-darwin.tar.gz",
+                "tool-1.0.0-x86_64-pc-windows-msvc.zip",
+            ],
+            &[
+                "tool_1.0.0_linux_amd64",
+                "tool_1.0.0_linux_arm64",
+                "tool_1.0.0_linux_386",
+                "tool_1.0.0_darwin_amd64",
+                "tool_1.0.0_darwin_arm64",
+                "tool_1.0.0_windows_amd64.exe",
+            ],
+            &["tool"],
+        ];
+        for assets in releases {
+            let base = serve_release(assets);
+            let release = ForgeRelease {
+                tag_name: "v1.0.0".into(),
+                name: None,
+                prerelease: false,
+                draft: false,
+                published_at: None,
+                assets: assets
+                    .iter()
+                    .map(|name| ForgeAsset {
+                        name: name.to_string(),
+                        url: format!("{base}/download/{name}"),
+                    })
+                    .collect(),
+            };
+            for matching in [None, Some("musl")] {
+                if matching.is_some() && !assets.iter().any(|a| a.contains("musl")) {
+                    continue;
+                }
+                assert_eq!(
+                    release.find_asset(matching).map(|a| a.name.as_str()),
+                    Some(ubi_pick(&base, matching).as_str()),
+                    "{assets:?} matching {matching:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_next_page() {
+        let url = "https://gitlab.com/api/v4/projects/1/releases?per_page=100";
+        let headers = |h: &[(&'static str, &str)]| -> HeaderMap {
+            h.iter()
+                .map(|(k, v)| (k.parse().unwrap(), v.parse().unwrap()))
+                .collect()
+        };
+        assert_eq!(next_page(url, &headers(&[])), None);
+        assert_eq!(
+            next_page(url, &headers(&[("x-next-page", "")])),
+            None,
+            "gitlab sends an empty X-Next-Page on the last page"
+        );
+        assert_eq!(
+            next_page(url, &headers(&[("x-next-page", "2")])).unwrap(),
+            format!("{url}&page=2")
+        );
+        let link = r#"<https://codeberg.org/api/v1/repos/a/b/releases?limit=50&page=3>; rel="next",<https://codeberg.org/api/v1/repos/a/b/releases?limit=50&page=9>; rel="last""#;
+        assert_eq!(
+            next_page(url, &headers(&[("link", link), ("x-next-page", "2")])).unwrap(),
+            "https://codeberg.org/api/v1/repos/a/b/releases?limit=50&page=3"
+        );
+    }
+
+    #[test]
+    fn test_is_listed() {
+        let release = |prerelease, draft| ForgeRelease {
+            tag_name: "v1.0.0".into(),
+            name: None,
+            prerelease,
+            draft,
+            published_at: None,
+            assets: vec![],
+        };
+        assert!(release(false, false).is_listed(false));
+        assert!(!release(true, false).is_listed(false));
+        assert!(release(true, false).is_listed(true));
+        assert!(!release(false, true).is_listed(true));
+    }
+}
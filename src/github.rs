@@ -1,20 +1,43 @@
+use crate::config::SETTINGS;
 use crate::env;
 use reqwest::header::HeaderMap;
 use serde_derive::Deserialize;
 use xx::regex;
 
+pub const DEFAULT_API_URL: &str = "https://api.github.com";
+
 #[derive(Debug, Deserialize)]
 pub struct GithubRelease {
     pub tag_name: String,
-    // pub name: Option<String>,
+    pub name: Option<String>,
     // pub body: Option<String>,
-    // pub prerelease: bool,
+    #[serde(default)]
+    pub prerelease: bool,
+    #[serde(default)]
+    pub draft: bool,
     // pub created_at: String,
-    // pub published_at: Option<String>,
+    pub published_at: Option<String>,
+    #[serde(default)]
+    pub assets: Vec<GithubAsset>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GithubAsset {
+    pub name: String,
+    pub browser_download_url: String,
+}
+
+/// `github_api_url`, e.g.: `https://github.example.com/api/v3` for GitHub Enterprise Server
+pub fn api_url() -> &'static str {
+    SETTINGS.github_api_url.trim_end_matches('/')
 }
 
 pub fn list_releases(repo: &str) -> eyre::Result<Vec<GithubRelease>> {
-    let url = format!("https://api.github.com/repos/{}/releases", repo);
+    list_releases_from_url(api_url(), repo)
+}
+
+pub fn list_releases_from_url(api_url: &str, repo: &str) -> eyre::Result<Vec<GithubRelease>> {
+    let url = format!("{api_url}/repos/{repo}/releases?per_page=100");
     let (mut releases, mut headers) =
         crate::http::HTTP_FETCH.json_headers::<Vec<GithubRelease>, _>(url)?;
 
@@ -30,14 +53,20 @@ pub fn list_releases(repo: &str) -> eyre::Result<Vec<GithubRelease>> {
 }
 
 pub fn get_release(repo: &str, tag: &str) -> eyre::Result<GithubRelease> {
-    let url = format!(
-        "https://api.github.com/repos/{}/releases/tags/{}",
-        repo, tag
-    );
+    get_release_for_url(api_url(), repo, tag)
+}
+
+pub fn get_release_for_url(api_url: &str, repo: &str, tag: &str) -> eyre::Result<GithubRelease> {
+    let url = format!("{api_url}/repos/{repo}/releases/tags/{tag}");
+    crate::http::HTTP_FETCH.json(url)
+}
+
+pub fn get_latest_release(repo: &str) -> eyre::Result<GithubRelease> {
+    let url = format!("{}/repos/{repo}/releases/latest", api_url());
     crate::http::HTTP_FETCH.json(url)
 }
 
-fn next_page(headers: &HeaderMap) -> Option<String> {
+pub fn next_page(headers: &HeaderMap) -> Option<String> {
     let link = headers
         .get("link")
         .map(|l| l.to_str().unwrap_or_default().to_string())
@@ -14,7 +14,7 @@ use crate::cli::version;
 use crate::config::SETTINGS;
 use crate::file::display_path;
 use crate::ui::progress_report::SingleReport;
-use crate::{env, file};
+use crate::{env, file, forge};
 
 #[cfg(not(test))]
 pub static HTTP_VERSION_CHECK: Lazy<Client> =
@@ -61,6 +61,8 @@ impl Client {
                 if let Some(token) = &*env::GITHUB_TOKEN {
                     req = req.header("authorization", format!("token {}", token));
                 }
+            } else if let Some(token) = url.host_str().and_then(forge::token) {
+                req = req.header("authorization", format!("Bearer {}", token));
             }
             let resp = req.send().await?;
             debug!("GET {url} {}", resp.status());
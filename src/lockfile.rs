@@ -1,8 +1,11 @@
+use crate::cli::args::BackendArg;
+use crate::cli::version::{ARCH, OS};
 use crate::config::{Config, SETTINGS};
-use crate::file;
 use crate::file::display_path;
 use crate::toolset::{ToolSource, ToolVersion, ToolVersionList, ToolsetBuilder};
-use eyre::{Report, Result};
+use crate::ui::progress_report::SingleReport;
+use crate::{file, hash};
+use eyre::{bail, ensure, Report, Result};
 use itertools::Itertools;
 use once_cell::sync::Lazy;
 use serde_derive::{Deserialize, Serialize};
@@ -10,12 +13,127 @@ use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct Lockfile {
-    tools: BTreeMap<String, String>,
+    /// locked versions in the same order as the requests in the config file
+    #[serde(
+        deserialize_with = "deserialize_tools",
+        serialize_with = "serialize_tools"
+    )]
+    tools: BTreeMap<String, Vec<LockfileTool>>,
 }
 
+/// a locked tool version, older lockfiles only contain the version string
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "RawLockfileTool")]
+pub struct LockfileTool {
+    pub version: String,
+    /// the request in the config file this version was resolved from, e.g.: "3.12" or "latest".
+    /// Only written for tools with several versions to tell them apart.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backend: Option<String>,
+    /// keyed by "<os>-<arch>", e.g.: "linux-x64", "macos-arm64"
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub platforms: BTreeMap<String, LockfilePlatform>,
+}
+
+/// the artifact that was downloaded to install a tool on a particular platform
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LockfilePlatform {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// "sha256:<hex>"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<String>,
+    /// artifacts of the packages installed along with the tool by name, e.g.: conda dependencies
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub dependencies: BTreeMap<String, LockfilePlatform>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawLockfileTool {
+    Version(String),
+    Full {
+        version: String,
+        #[serde(default)]
+        request: Option<String>,
+        #[serde(default)]
+        backend: Option<String>,
+        #[serde(default)]
+        platforms: BTreeMap<String, LockfilePlatform>,
+    },
+}
+
+impl From<RawLockfileTool> for LockfileTool {
+    fn from(raw: RawLockfileTool) -> Self {
+        match raw {
+            RawLockfileTool::Version(version) => Self {
+                version,
+                ..Default::default()
+            },
+            RawLockfileTool::Full {
+                version,
+                request,
+                backend,
+                platforms,
+            } => Self {
+                version,
+                request,
+                backend,
+                platforms,
+            },
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawLockfileTools {
+    Many(Vec<LockfileTool>),
+    One(LockfileTool),
+}
+
+fn deserialize_tools<'de, D>(
+    deserializer: D,
+) -> Result<BTreeMap<String, Vec<LockfileTool>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let tools: BTreeMap<String, RawLockfileTools> = serde::Deserialize::deserialize(deserializer)?;
+    Ok(tools
+        .into_iter()
+        .map(|(short, tools)| match tools {
+            RawLockfileTools::Many(tools) => (short, tools),
+            RawLockfileTools::One(tool) => (short, vec![tool]),
+        })
+        .collect())
+}
+
+/// a tool with a single version is written as a table like older lockfiles, several as an array
+fn serialize_tools<S>(tools: &BTreeMap<String, Vec<LockfileTool>>, s: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    use serde::ser::SerializeMap;
+    let mut map = s.serialize_map(Some(tools.len()))?;
+    for (short, tools) in tools {
+        match tools.as_slice() {
+            [tool] => map.serialize_entry(short, tool)?,
+            tools => map.serialize_entry(short, tools)?,
+        }
+    }
+    map.end()
+}
+
+/// artifacts downloaded during this session, keyed by (short, version)
+static SESSION_PLATFORMS: Lazy<Mutex<HashMap<(String, String), LockfilePlatform>>> =
+    Lazy::new(Default::default);
+
 impl Lockfile {
     pub fn read<P: AsRef<Path>>(path: P) -> Result<Self> {
         let content = file::read_to_string(path)?;
@@ -37,6 +155,16 @@ impl Lockfile {
     pub fn is_empty(&self) -> bool {
         self.tools.is_empty()
     }
+
+    fn versions(&self) -> BTreeMap<&str, Vec<&str>> {
+        self.tools
+            .iter()
+            .map(|(short, tools)| {
+                let versions = tools.iter().map(|t| t.version.as_str()).collect();
+                (short.as_str(), versions)
+            })
+            .collect()
+    }
 }
 
 pub fn update_lockfiles(new_versions: &[ToolVersion]) -> Result<()> {
@@ -62,12 +190,22 @@ pub fn update_lockfiles(new_versions: &[ToolVersion]) -> Result<()> {
     for (backend, group) in &new_versions.iter().chunk_by(|tv| &tv.backend) {
         let tvs = group.cloned().collect_vec();
         let source = tvs[0].request.source().clone();
-        let mut tvl = ToolVersionList::new(backend.clone(), source.clone());
-        tvl.versions.extend(tvs);
-        tools_by_source
-            .entry(source)
+        let tvl = tools_by_source
+            .entry(source.clone())
             .or_insert_with(HashMap::new)
-            .insert(backend.short.to_string(), tvl);
+            .entry(backend.short.to_string())
+            .or_insert_with(|| ToolVersionList::new(backend.clone(), source));
+        for tv in tvs {
+            // replace the version resolved for the same request, if any
+            match tvl
+                .versions
+                .iter_mut()
+                .find(|v| v.request.version() == tv.request.version())
+            {
+                Some(v) => *v = tv,
+                None => tvl.versions.push(tv),
+            }
+        }
     }
 
     let lockfiles = config.config_files.keys().rev().collect_vec();
@@ -85,6 +223,7 @@ pub fn update_lockfiles(new_versions: &[ToolVersion]) -> Result<()> {
         );
         let mut existing_lockfile = Lockfile::read(&lockfile_path)
             .unwrap_or_else(|err| handle_missing_lockfile(err, &lockfile_path));
+        let original_lockfile = existing_lockfile.clone();
 
         // there are tools that should remain in the lockfile even though they're not in this current toolset
         // * tools that are disabled via settings
@@ -93,25 +232,102 @@ pub fn update_lockfiles(new_versions: &[ToolVersion]) -> Result<()> {
             .tools
             .retain(|k, _| all_tool_names.contains(k) || SETTINGS.disable_tools.contains(k));
 
+        let session = SESSION_PLATFORMS.lock().unwrap();
         for (short, tvl) in tools {
-            for tv in &tvl.versions {
-                existing_lockfile
-                    .tools
-                    .insert(short.to_string(), tv.version.to_string());
-            }
+            let existing = existing_lockfile.tools.remove(short).unwrap_or_default();
+            let tvs = tvl
+                .versions
+                .iter()
+                .unique_by(|tv| tv.request.version())
+                .collect_vec();
+            let several = tvs.len() > 1;
+            let locked = tvs
+                .into_iter()
+                .map(|tv| {
+                    let mut tool = existing
+                        .iter()
+                        .find(|t| t.version == tv.version)
+                        .cloned()
+                        .unwrap_or_else(|| LockfileTool {
+                            version: tv.version.to_string(),
+                            ..Default::default()
+                        });
+                    tool.request = several.then(|| tv.request.version());
+                    tool.backend = Some(backend_id(&tv.backend));
+                    if let Some(platform) = session.get(&(short.to_string(), tv.version.clone())) {
+                        tool.platforms.insert(platform_key(), platform.clone());
+                    }
+                    tool
+                })
+                .collect_vec();
+            existing_lockfile.tools.insert(short.to_string(), locked);
         }
 
+        if existing_lockfile == original_lockfile {
+            continue;
+        }
+        if SETTINGS.locked {
+            ensure!(
+                existing_lockfile.versions() == original_lockfile.versions(),
+                "lockfile {} is out of date and cannot be updated since `locked` is enabled",
+                display_path(&lockfile_path)
+            );
+            debug!(
+                "not updating metadata in {} since `locked` is enabled",
+                display_path(&lockfile_path)
+            );
+            continue;
+        }
         existing_lockfile.save(&lockfile_path)?;
     }
 
     Ok(())
 }
 
-pub fn get_locked_version(path: &Path, short: &str) -> Result<Option<String>> {
+/// finds the locked version for `request`, the version of a request in the config file
+/// (e.g.: "3.12" or "prefix:3.12")
+pub fn get_locked_version(path: &Path, ba: &BackendArg, request: &str) -> Result<Option<String>> {
+    let tools = get_locked_tools(path, ba)?;
+    Ok(find_locked_tool(&tools, request).map(|t| t.version.clone()))
+}
+
+/// the tool locked for `request`, versions locked without their request (tools with a single
+/// version and older lockfiles) are matched by prefix (e.g.: "3.12" matches "3.12.1")
+fn find_locked_tool<'a>(tools: &'a [LockfileTool], request: &str) -> Option<&'a LockfileTool> {
+    if let Some(tool) = tools.iter().find(|t| t.request.as_deref() == Some(request)) {
+        return Some(tool);
+    }
+    let tools = tools.iter().filter(|t| t.request.is_none()).collect_vec();
+    let prefix = request.strip_prefix("prefix:").unwrap_or(request);
+    tools
+        .iter()
+        .find(|t| version_matches_request(&t.version, prefix))
+        .or_else(|| {
+            // requests like "latest" or "lts" can't be matched by prefix
+            let is_version = prefix.starts_with(|c: char| c.is_ascii_digit());
+            match tools.as_slice() {
+                [tool] if !is_version => Some(tool),
+                _ => None,
+            }
+        })
+        .copied()
+}
+
+fn version_matches_request(version: &str, request: &str) -> bool {
+    version == request
+        || version.starts_with(&format!("{request}."))
+        || version.starts_with(&format!("{request}-"))
+}
+
+fn get_locked_tools(path: &Path, ba: &BackendArg) -> Result<Vec<LockfileTool>> {
     static CACHE: Lazy<Mutex<HashMap<PathBuf, Lockfile>>> = Lazy::new(Default::default);
 
     if !SETTINGS.lockfile {
-        return Ok(None);
+        ensure!(
+            !SETTINGS.locked,
+            "`locked` requires `lockfile` to be enabled"
+        );
+        return Ok(vec![]);
     }
     SETTINGS.ensure_experimental("lockfile")?;
 
@@ -122,7 +338,144 @@ pub fn get_locked_version(path: &Path, short: &str) -> Result<Option<String>> {
             .unwrap_or_else(|err| handle_missing_lockfile(err, &lockfile_path))
     });
 
-    Ok(lockfile.tools.get(short).cloned())
+    let backend = backend_id(ba);
+    let tools = lockfile.tools.get(&ba.short).cloned().unwrap_or_default();
+    Ok(tools
+        .into_iter()
+        .filter(|t| match &t.backend {
+            Some(b) if b != &backend => {
+                debug!(
+                    "ignoring locked version {} of {} since it was locked with backend {b}",
+                    t.version, ba.short,
+                );
+                false
+            }
+            _ => true,
+        })
+        .collect())
+}
+
+/// Checks an artifact downloaded to install `tv` against the url and checksum in the lockfile
+/// for the current platform, failing on a mismatch. The artifact is then recorded so that
+/// `update_lockfiles` can write it out.
+pub fn verify_artifact(
+    tv: &ToolVersion,
+    url: Option<&str>,
+    path: &Path,
+    pr: Option<&dyn SingleReport>,
+) -> Result<()> {
+    verify(tv, None, url, path, pr)
+}
+
+/// like `verify_artifact` for a package installed along with `tv` such as a conda dependency
+pub fn verify_dependency(
+    tv: &ToolVersion,
+    name: &str,
+    url: &str,
+    path: &Path,
+    pr: Option<&dyn SingleReport>,
+) -> Result<()> {
+    verify(tv, Some(name), Some(url), path, pr)
+}
+
+/// the artifacts locked for `tv` on the current platform, if any
+pub fn get_locked_platform(tv: &ToolVersion) -> Result<Option<LockfilePlatform>> {
+    if !SETTINGS.lockfile {
+        return Ok(None);
+    }
+    Ok(match tv.request.source().path() {
+        Some(config_path) => get_locked_tools(config_path, &tv.backend)?
+            .into_iter()
+            .find(|t| t.version == tv.version)
+            .and_then(|mut t| t.platforms.remove(&platform_key())),
+        None => None,
+    })
+}
+
+fn verify(
+    tv: &ToolVersion,
+    dependency: Option<&str>,
+    url: Option<&str>,
+    path: &Path,
+    pr: Option<&dyn SingleReport>,
+) -> Result<()> {
+    if !SETTINGS.lockfile {
+        return Ok(());
+    }
+    let platform = get_locked_platform(tv)?.unwrap_or_default();
+    let (name, locked) = match dependency {
+        Some(name) => (
+            format!("{name} for {tv}"),
+            platform.dependencies.get(name).cloned().unwrap_or_default(),
+        ),
+        None => (tv.to_string(), platform),
+    };
+    if let (Some(expected), Some(actual)) = (&locked.url, url) {
+        ensure!(
+            expected == actual,
+            "URL mismatch for {name} in lockfile:\nExpected: {expected}\nActual:   {actual}",
+        );
+    }
+    let frozen = SETTINGS.locked && matches!(tv.request.source(), ToolSource::MiseToml(_));
+    let checksum = match locked_checksum(&name, &locked, frozen)? {
+        Some(checksum) => {
+            hash::ensure_checksum_sha256(path, parse_checksum(checksum)?, pr)?;
+            checksum.to_string()
+        }
+        None => format!("sha256:{}", hash::file_hash_sha256_prog(path, pr)?),
+    };
+    let mut session = SESSION_PLATFORMS.lock().unwrap();
+    let session = session
+        .entry((tv.backend.short.clone(), tv.version.clone()))
+        .or_default();
+    let artifact = match dependency {
+        Some(name) => session.dependencies.entry(name.to_string()).or_default(),
+        None => session,
+    };
+    artifact.url = url.map(|u| u.to_string()).or(locked.url);
+    artifact.checksum = Some(checksum);
+    Ok(())
+}
+
+/// the checksum locked for an artifact, with `frozen` it must be locked since the lockfile can't
+/// be updated to add it
+fn locked_checksum<'a>(
+    name: &str,
+    locked: &'a LockfilePlatform,
+    frozen: bool,
+) -> Result<Option<&'a str>> {
+    match &locked.checksum {
+        Some(checksum) => Ok(Some(checksum)),
+        None if frozen => bail!(
+            "{name} has no checksum for {} in the lockfile and `locked` is enabled",
+            platform_key()
+        ),
+        None => Ok(None),
+    }
+}
+
+/// errors if `locked` is enabled since `cmd` would modify lockfiles
+pub fn ensure_unlocked(cmd: &str) -> Result<()> {
+    if SETTINGS.lockfile && SETTINGS.locked {
+        bail!("{cmd} cannot be used when `locked` is enabled since it would modify lockfiles");
+    }
+    Ok(())
+}
+
+fn parse_checksum(checksum: &str) -> Result<&str> {
+    match checksum.split_once(':') {
+        Some(("sha256", hash)) => Ok(hash),
+        Some((algo, _)) => bail!("unsupported checksum algorithm in lockfile: {algo}"),
+        None => Ok(checksum),
+    }
+}
+
+fn backend_id(ba: &BackendArg) -> String {
+    format!("{}:{}", ba.backend_type, ba.name)
+}
+
+fn platform_key() -> String {
+    format!("{}-{}", *OS, *ARCH)
 }
 
 fn handle_missing_lockfile(err: Report, lockfile_path: &Path) -> Lockfile {
@@ -136,3 +489,129 @@ fn handle_missing_lockfile(err: Report, lockfile_path: &Path) -> Lockfile {
     }
     Lockfile::default()
 }
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_lockfile() {
+        let lockfile: Lockfile = toml::from_str(
+            r#"
+            [tools]
+            tiny = "1.0.0"
+            dummy = { version = "2.0.0", backend = "asdf:dummy" }
+
+            [[tools.python]]
+            version = "3.12.1"
+
+            [[tools.python]]
+            version = "3.11.7"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            lockfile.versions(),
+            BTreeMap::from([
+                ("dummy", vec!["2.0.0"]),
+                ("python", vec!["3.12.1", "3.11.7"]),
+                ("tiny", vec!["1.0.0"]),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_serialize_lockfile() {
+        let tool = |version: &str, request: Option<&str>| LockfileTool {
+            version: version.to_string(),
+            request: request.map(|r| r.to_string()),
+            ..Default::default()
+        };
+        let lockfile = Lockfile {
+            tools: BTreeMap::from([
+                ("tiny".to_string(), vec![tool("1.0.0", None)]),
+                (
+                    "python".to_string(),
+                    vec![tool("3.11.7", Some("3.11")), tool("3.12.1", Some("3"))],
+                ),
+            ]),
+        };
+        let content = toml::to_string_pretty(&lockfile).unwrap();
+        assert_eq!(
+            content,
+            r#"[[tools.python]]
+version = "3.11.7"
+request = "3.11"
+
+[[tools.python]]
+version = "3.12.1"
+request = "3"
+
+[tools.tiny]
+version = "1.0.0"
+"#
+        );
+        assert_eq!(toml::from_str::<Lockfile>(&content).unwrap(), lockfile);
+    }
+
+    #[test]
+    fn test_find_locked_tool() {
+        let tool = |version: &str, request: Option<&str>| LockfileTool {
+            version: version.to_string(),
+            request: request.map(|r| r.to_string()),
+            ..Default::default()
+        };
+        let find = |tools: &[LockfileTool], request| {
+            find_locked_tool(tools, request).map(|t| t.version.clone())
+        };
+        let tools = [tool("3.11.7", Some("3.11")), tool("3.12.1", Some("3"))];
+        assert_eq!(find(&tools, "3.11"), Some("3.11.7".into()));
+        assert_eq!(find(&tools, "3"), Some("3.12.1".into()));
+        assert_eq!(find(&tools, "3.10"), None);
+        let tools = [tool("22.1.0", Some("latest")), tool("20.9.0", Some("20"))];
+        assert_eq!(find(&tools, "latest"), Some("22.1.0".into()));
+        assert_eq!(find(&tools, "20"), Some("20.9.0".into()));
+
+        let tools = [tool("3.12.1", None)];
+        assert_eq!(find(&tools, "3.12"), Some("3.12.1".into()));
+        assert_eq!(find(&tools, "prefix:3.12"), Some("3.12.1".into()));
+        assert_eq!(find(&tools, "latest"), Some("3.12.1".into()));
+        assert_eq!(find(&tools, "3.11"), None);
+    }
+
+    #[test]
+    fn test_locked_checksum() {
+        let locked = LockfilePlatform {
+            checksum: Some("sha256:abc".into()),
+            ..Default::default()
+        };
+        assert_eq!(
+            locked_checksum("tiny@1.0.0", &locked, true).unwrap(),
+            Some("sha256:abc")
+        );
+        let unlocked = LockfilePlatform::default();
+        assert_eq!(
+            locked_checksum("tiny@1.0.0", &unlocked, false).unwrap(),
+            None
+        );
+        let err = locked_checksum("tiny@1.0.0", &unlocked, true).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "tiny@1.0.0 has no checksum for {} in the lockfile and `locked` is enabled",
+                platform_key()
+            )
+        );
+    }
+
+    #[test]
+    fn test_version_matches_request() {
+        assert!(version_matches_request("3.12.1", "3.12"));
+        assert!(version_matches_request("3.12.1", "3.12.1"));
+        assert!(version_matches_request("20.0.0-rc1", "20.0.0"));
+        assert!(!version_matches_request("3.1.0", "3.12"));
+        assert!(!version_matches_request("3.12.1", "3.11"));
+    }
+}
@@ -33,6 +33,7 @@ mod exit;
 #[cfg_attr(windows, path = "fake_asdf_windows.rs")]
 mod fake_asdf;
 mod file;
+mod forge;
 mod git;
 pub(crate) mod github;
 mod hash;
@@ -49,6 +50,7 @@ mod rand;
 mod registry;
 pub(crate) mod result;
 mod runtime_symlinks;
+mod secrets;
 mod shell;
 mod shims;
 mod shorthands;
@@ -9,13 +9,13 @@ use crate::backend::Backend;
 use crate::cli::args::BackendArg;
 use crate::cli::version::{ARCH, OS};
 use crate::cmd::CmdLineRunner;
-use crate::file;
-use crate::github::GithubRelease;
-use crate::http::{HTTP, HTTP_FETCH};
+use crate::github;
+use crate::http::HTTP;
 use crate::install_context::InstallContext;
 use crate::plugins::core::CorePlugin;
 use crate::toolset::{ToolRequest, ToolVersion};
 use crate::ui::progress_report::SingleReport;
+use crate::{file, lockfile};
 
 #[derive(Debug)]
 pub struct BunPlugin {
@@ -52,6 +52,7 @@ impl BunPlugin {
 
         pr.set_message(format!("downloading {filename}"));
         HTTP.download_file(&url, &tarball_path, Some(pr))?;
+        lockfile::verify_artifact(tv, Some(&url), &tarball_path, Some(pr))?;
 
         Ok(tarball_path)
     }
@@ -85,8 +86,7 @@ impl Backend for BunPlugin {
     }
 
     fn _list_remote_versions(&self) -> Result<Vec<String>> {
-        let releases: Vec<GithubRelease> =
-            HTTP_FETCH.json("https://api.github.com/repos/oven-sh/bun/releases?per_page=100")?;
+        let releases = github::list_releases("oven-sh/bun")?;
         let versions = releases
             .into_iter()
             .map(|r| r.tag_name)
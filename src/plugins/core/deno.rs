@@ -12,12 +12,12 @@ use crate::cli::args::BackendArg;
 use crate::cli::version::{ARCH, OS};
 use crate::cmd::CmdLineRunner;
 use crate::config::Config;
-use crate::file;
 use crate::http::{HTTP, HTTP_FETCH};
 use crate::install_context::InstallContext;
 use crate::plugins::core::CorePlugin;
 use crate::toolset::{ToolRequest, ToolVersion, Toolset};
 use crate::ui::progress_report::SingleReport;
+use crate::{file, lockfile};
 
 #[derive(Debug)]
 pub struct DenoPlugin {
@@ -58,6 +58,7 @@ impl DenoPlugin {
 
         pr.set_message(format!("downloading {filename}"));
         HTTP.download_file(&url, &tarball_path, Some(pr))?;
+        lockfile::verify_artifact(tv, Some(&url), &tarball_path, Some(pr))?;
 
         // TODO: hash::ensure_checksum_sha256(&tarball_path, &m.sha256)?;
 
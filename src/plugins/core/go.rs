@@ -12,7 +12,7 @@ use crate::install_context::InstallContext;
 use crate::plugins::core::CorePlugin;
 use crate::toolset::{ToolRequest, ToolVersion, Toolset};
 use crate::ui::progress_report::SingleReport;
-use crate::{cmd, env, file, hash};
+use crate::{cmd, env, file, hash, lockfile};
 use itertools::Itertools;
 use tempfile::tempdir_in;
 use versions::Versioning;
@@ -110,6 +110,7 @@ impl GoPlugin {
                 let checksum = checksum_handle.join().unwrap()?;
                 hash::ensure_checksum_sha256(&tarball_path, &checksum, Some(pr))?;
             }
+            lockfile::verify_artifact(tv, Some(tarball_url.as_str()), &tarball_path, Some(pr))?;
             Ok(tarball_path)
         })
     }
@@ -15,7 +15,7 @@ use crate::plugins::core::CorePlugin;
 use crate::plugins::VERSION_REGEX;
 use crate::toolset::{ToolRequest, ToolVersion, Toolset};
 use crate::ui::progress_report::SingleReport;
-use crate::{file, hash};
+use crate::{file, hash, lockfile};
 use color_eyre::eyre::{eyre, Result};
 use contracts::requires;
 use indoc::formatdoc;
@@ -102,6 +102,7 @@ impl JavaPlugin {
         HTTP.download_file(&m.url, &tarball_path, Some(pr))?;
 
         hash::ensure_checksum_sha256(&tarball_path, &m.sha256, Some(pr))?;
+        lockfile::verify_artifact(tv, Some(m.url.as_str()), &tarball_path, Some(pr))?;
 
         Ok(tarball_path)
     }
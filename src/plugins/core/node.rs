@@ -10,7 +10,7 @@ use crate::install_context::InstallContext;
 use crate::plugins::core::CorePlugin;
 use crate::toolset::ToolVersion;
 use crate::ui::progress_report::SingleReport;
-use crate::{env, file, hash, http};
+use crate::{env, file, hash, http, lockfile};
 use eyre::{bail, ensure, Result};
 use serde_derive::Deserialize;
 use std::collections::BTreeMap;
@@ -35,7 +35,7 @@ impl NodePlugin {
     fn install_precompiled(&self, ctx: &InstallContext, opts: &BuildOpts) -> Result<()> {
         let settings = Settings::get();
         match self.fetch_tarball(
-            ctx.pr.as_ref(),
+            ctx,
             &opts.binary_tarball_url,
             &opts.binary_tarball_path,
             &opts.version,
@@ -63,7 +63,7 @@ impl NodePlugin {
 
     fn install_windows(&self, ctx: &InstallContext, opts: &BuildOpts) -> Result<()> {
         match self.fetch_tarball(
-            ctx.pr.as_ref(),
+            ctx,
             &opts.binary_tarball_url,
             &opts.binary_tarball_path,
             &opts.version,
@@ -88,7 +88,7 @@ impl NodePlugin {
     fn install_compiled(&self, ctx: &InstallContext, opts: &BuildOpts) -> Result<()> {
         let tarball_name = &opts.source_tarball_name;
         self.fetch_tarball(
-            ctx.pr.as_ref(),
+            ctx,
             &opts.source_tarball_url,
             &opts.source_tarball_path,
             &opts.version,
@@ -104,11 +104,12 @@ impl NodePlugin {
 
     fn fetch_tarball(
         &self,
-        pr: &dyn SingleReport,
+        ctx: &InstallContext,
         url: &Url,
         local: &Path,
         version: &str,
     ) -> Result<()> {
+        let pr = ctx.pr.as_ref();
         let tarball_name = local.file_name().unwrap().to_string_lossy().to_string();
         if local.exists() {
             pr.set_message(format!("using previously downloaded {tarball_name}"));
@@ -120,6 +121,7 @@ impl NodePlugin {
             pr.set_message(format!("verifying {tarball_name}"));
             self.verify(local, version, pr)?;
         }
+        lockfile::verify_artifact(&ctx.tv, Some(url.as_str()), local, Some(pr))?;
         Ok(())
     }
 
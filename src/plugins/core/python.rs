@@ -11,7 +11,7 @@ use crate::install_context::InstallContext;
 use crate::plugins::core::CorePlugin;
 use crate::toolset::{ToolRequest, ToolVersion, Toolset};
 use crate::ui::progress_report::SingleReport;
-use crate::{cmd, file};
+use crate::{cmd, file, lockfile};
 use eyre::{bail, eyre};
 use itertools::Itertools;
 use std::collections::BTreeMap;
@@ -182,6 +182,7 @@ impl PythonPlugin {
 
         ctx.pr.set_message(format!("downloading {filename}"));
         HTTP.download_file(&url, &tarball_path, Some(ctx.pr.as_ref()))?;
+        lockfile::verify_artifact(&ctx.tv, Some(&url), &tarball_path, Some(ctx.pr.as_ref()))?;
 
         ctx.pr.set_message(format!("installing {filename}"));
         file::untar(&tarball_path, &download)?;
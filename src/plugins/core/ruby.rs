@@ -9,8 +9,8 @@ use crate::config::{Config, Settings};
 use crate::duration::DAILY;
 use crate::env::PATH_KEY;
 use crate::git::Git;
-use crate::github::GithubRelease;
-use crate::http::{HTTP, HTTP_FETCH};
+use crate::github;
+use crate::http::HTTP;
 use crate::install_context::InstallContext;
 use crate::lock_file::LockFile;
 use crate::plugins::core::CorePlugin;
@@ -222,8 +222,7 @@ impl RubyPlugin {
     }
 
     fn latest_ruby_build_version(&self) -> Result<String> {
-        let release: GithubRelease =
-            HTTP_FETCH.json("https://api.github.com/repos/rbenv/ruby-build/releases/latest")?;
+        let release = github::get_latest_release("rbenv/ruby-build")?;
         Ok(release.tag_name.trim_start_matches('v').to_string())
     }
 
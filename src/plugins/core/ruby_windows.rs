@@ -12,7 +12,7 @@ use crate::install_context::InstallContext;
 use crate::plugins::core::CorePlugin;
 use crate::toolset::{ToolRequest, ToolVersion, Toolset};
 use crate::ui::progress_report::SingleReport;
-use crate::{env, file, github};
+use crate::{env, file, github, lockfile};
 use contracts::requires;
 use eyre::Result;
 use itertools::Itertools;
@@ -112,6 +112,7 @@ impl RubyPlugin {
 
         pr.set_message(format!("downloading {filename}"));
         HTTP.download_file(&url, &tarball_path, Some(pr))?;
+        lockfile::verify_artifact(tv, Some(&url), &tarball_path, Some(pr))?;
 
         Ok(tarball_path)
     }
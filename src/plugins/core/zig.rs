@@ -4,13 +4,13 @@ use crate::backend::Backend;
 use crate::cli::args::BackendArg;
 use crate::cli::version::{ARCH, OS};
 use crate::cmd::CmdLineRunner;
-use crate::file;
-use crate::github::GithubRelease;
+use crate::github;
 use crate::http::{HTTP, HTTP_FETCH};
 use crate::install_context::InstallContext;
 use crate::plugins::core::CorePlugin;
 use crate::toolset::{ToolRequest, ToolVersion};
 use crate::ui::progress_report::SingleReport;
+use crate::{file, lockfile};
 use contracts::requires;
 use eyre::Result;
 use itertools::Itertools;
@@ -70,6 +70,7 @@ impl ZigPlugin {
 
         pr.set_message(format!("downloading {filename}"));
         HTTP.download_file(&url, &tarball_path, Some(pr))?;
+        lockfile::verify_artifact(tv, Some(&url), &tarball_path, Some(pr))?;
 
         Ok(tarball_path)
     }
@@ -122,8 +123,7 @@ impl Backend for ZigPlugin {
     }
 
     fn _list_remote_versions(&self) -> Result<Vec<String>> {
-        let releases: Vec<GithubRelease> =
-            HTTP_FETCH.json("https://api.github.com/repos/ziglang/zig/releases?per_page=100")?;
+        let releases = github::list_releases("ziglang/zig")?;
         let versions = releases
             .into_iter()
             .map(|r| r.tag_name)
@@ -0,0 +1,208 @@
+use std::collections::BTreeMap;
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use base64::prelude::*;
+use eyre::{eyre, Result, WrapErr};
+use flate2::write::{ZlibDecoder, ZlibEncoder};
+use flate2::Compression;
+use indexmap::IndexMap;
+use once_cell::sync::Lazy;
+
+use crate::config::SETTINGS;
+use crate::env_diff::EnvDiffOperation;
+use crate::file::display_path;
+use crate::hash::file_hash_sha256;
+use crate::{dirs, env, file};
+
+/// keys of the secrets files decrypted in the current hook-env session, by the sha256 of the
+/// encrypted file. Their values are already exported so they are read from `__MISE_DIFF`
+/// instead of decrypting the file on every prompt.
+static SESSION: Lazy<BTreeMap<String, Vec<String>>> =
+    Lazy::new(|| match env::var("__MISE_SECRETS") {
+        Ok(raw) => deserialize_session(&raw)
+            .map_err(|e| warn!("Failed to deserialize __MISE_SECRETS {e}"))
+            .unwrap_or_default(),
+        _ => Default::default(),
+    });
+
+static DECRYPTED: Lazy<Mutex<BTreeMap<String, Vec<String>>>> = Lazy::new(Default::default);
+
+/// decrypts an age or sops encrypted dotenv, json or yaml file into env vars
+pub fn decrypt(path: &Path) -> Result<IndexMap<String, String>> {
+    let hash = file_hash_sha256(path)?;
+    let env = match from_session(&hash) {
+        Some(env) => {
+            trace!("secrets: using session values for {}", display_path(path));
+            env
+        }
+        None => decrypt_file(path)
+            .wrap_err_with(|| format!("failed to decrypt {}", display_path(path)))?,
+    };
+    DECRYPTED
+        .lock()
+        .unwrap()
+        .insert(hash, env.keys().cloned().collect());
+    Ok(env)
+}
+
+/// sets `__MISE_SECRETS` for the next hook-env run
+pub fn build_session_operation() -> Result<Option<EnvDiffOperation>> {
+    let decrypted = DECRYPTED.lock().unwrap();
+    if decrypted.is_empty() {
+        if SESSION.is_empty() {
+            return Ok(None);
+        }
+        return Ok(Some(EnvDiffOperation::Remove("__MISE_SECRETS".into())));
+    }
+    Ok(Some(EnvDiffOperation::Add(
+        "__MISE_SECRETS".into(),
+        serialize_session(&decrypted)?,
+    )))
+}
+
+/// `age_key_file` or `$MISE_CONFIG_DIR/age.txt` if it exists
+fn age_key_file() -> Option<PathBuf> {
+    SETTINGS
+        .age_key_file
+        .clone()
+        .or_else(|| Some(dirs::CONFIG.join("age.txt")).filter(|p| p.exists()))
+}
+
+fn from_session(hash: &str) -> Option<IndexMap<String, String>> {
+    let keys = SESSION.get(hash)?;
+    keys.iter()
+        .map(|k| env::__MISE_DIFF.new.get(k).map(|v| (k.clone(), v.clone())))
+        .collect()
+}
+
+fn decrypt_file(path: &Path) -> Result<IndexMap<String, String>> {
+    debug!("secrets: decrypting {}", display_path(path));
+    let mut header = [0; 64];
+    let n = file::open(path)?.read(&mut header)?;
+    if is_age(&header[..n]) {
+        parse(path, &decrypt_age(path)?)
+    } else {
+        // sops converts dotenv, json and yaml files to json itself
+        parse_json(&decrypt_sops(path)?)
+    }
+}
+
+fn is_age(raw: &[u8]) -> bool {
+    raw.starts_with(b"age-encryption.org/")
+        || raw.starts_with(b"-----BEGIN AGE ENCRYPTED FILE-----")
+}
+
+fn decrypt_age(path: &Path) -> Result<String> {
+    let key_file = age_key_file().ok_or_else(|| {
+        eyre!(
+            "no age identity found, set age_key_file or create {}",
+            display_path(dirs::CONFIG.join("age.txt"))
+        )
+    })?;
+    cmd!("age", "--decrypt", "--identity", key_file, path)
+        .read()
+        .wrap_err("failed to run age, is it installed?")
+}
+
+fn decrypt_sops(path: &Path) -> Result<String> {
+    let mut cmd = cmd!("sops", "--decrypt", "--output-type", "json", path);
+    if let Some(key_file) = age_key_file() {
+        cmd = cmd.env("SOPS_AGE_KEY_FILE", key_file);
+    }
+    cmd.read().wrap_err("failed to run sops, is it installed?")
+}
+
+/// parses the plaintext by the extension of the file without `.age`, e.g.: `secrets.json.age`
+fn parse(path: &Path, plaintext: &str) -> Result<IndexMap<String, String>> {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let name = name.trim_end_matches(".age");
+    if name.ends_with(".json") {
+        parse_json(plaintext)
+    } else if name.ends_with(".yaml") || name.ends_with(".yml") {
+        parse_yaml(plaintext)
+    } else {
+        parse_dotenv(plaintext)
+    }
+}
+
+fn parse_json(plaintext: &str) -> Result<IndexMap<String, String>> {
+    let map: IndexMap<String, serde_json::Value> = serde_json::from_str(plaintext)?;
+    Ok(map
+        .into_iter()
+        .map(|(k, v)| match v {
+            serde_json::Value::String(s) => (k, s),
+            v => (k, v.to_string()),
+        })
+        .collect())
+}
+
+fn parse_yaml(plaintext: &str) -> Result<IndexMap<String, String>> {
+    let map: IndexMap<String, serde_yaml::Value> = serde_yaml::from_str(plaintext)?;
+    map.into_iter()
+        .map(|(k, v)| match v {
+            serde_yaml::Value::String(s) => Ok((k, s)),
+            v => Ok((k, serde_yaml::to_string(&v)?.trim_end().to_string())),
+        })
+        .collect()
+}
+
+fn parse_dotenv(plaintext: &str) -> Result<IndexMap<String, String>> {
+    dotenvy::from_read_iter(plaintext.as_bytes())
+        .map(|item| Ok(item?))
+        .collect()
+}
+
+fn serialize_session(session: &BTreeMap<String, Vec<String>>) -> Result<String> {
+    let mut gz = ZlibEncoder::new(Vec::new(), Compression::fast());
+    gz.write_all(&rmp_serde::to_vec_named(session)?)?;
+    Ok(BASE64_STANDARD_NO_PAD.encode(gz.finish()?))
+}
+
+fn deserialize_session(raw: &str) -> Result<BTreeMap<String, Vec<String>>> {
+    let mut decoder = ZlibDecoder::new(Vec::new());
+    decoder.write_all(&BASE64_STANDARD_NO_PAD.decode(raw)?)?;
+    Ok(rmp_serde::from_slice(&decoder.finish()?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_is_age() {
+        assert!(is_age(b"age-encryption.org/v1\n-> X25519 abc"));
+        assert!(is_age(b"-----BEGIN AGE ENCRYPTED FILE-----\nYWdl"));
+        assert!(!is_age(b"{\"FOO\": \"ENC[AES256_GCM,data:abc]\"}"));
+    }
+
+    #[test]
+    fn test_parse() {
+        let expected: IndexMap<String, String> = [("FOO", "bar"), ("PORT", "8080")]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        let parse_as = |name: &str, plaintext: &str| parse(Path::new(name), plaintext).unwrap();
+        assert_eq!(parse_as(".env.age", "FOO=bar\nPORT=8080\n"), expected);
+        assert_eq!(
+            parse_as("secrets.json.age", r#"{"FOO": "bar", "PORT": 8080}"#),
+            expected
+        );
+        assert_eq!(
+            parse_as("secrets.yaml.age", "FOO: bar\nPORT: 8080\n"),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_session() {
+        let session = [("abc".to_string(), vec!["FOO".to_string()])]
+            .into_iter()
+            .collect();
+        let raw = serialize_session(&session).unwrap();
+        assert_eq!(deserialize_session(&raw).unwrap(), session);
+    }
+}
@@ -10,6 +10,7 @@ use std::collections::{HashMap, HashSet};
 pub struct Deps {
     pub graph: DiGraph<Task, ()>,
     sent: HashSet<String>, // tasks that have already started so should not run again
+    pub skipped: HashMap<String, String>, // tasks whose conditions failed and why
     tx: channel::Sender<Option<Task>>,
 }
 
@@ -19,6 +20,8 @@ impl Deps {
         let mut graph = DiGraph::new();
         let mut indexes = HashMap::new();
         let mut stack = vec![];
+        let mut skipped = HashMap::new();
+        let mut checked = HashSet::new();
 
         // first we add all tasks to the graph, create a stack of work for this function, and
         // store the index of each task in the graph
@@ -32,6 +35,15 @@ impl Deps {
             let a_idx = *indexes
                 .entry(a.name.clone())
                 .or_insert_with(|| graph.add_node(a.clone()));
+            if checked.insert(a.name.clone()) {
+                if let Some(reason) = a.skip_reason()? {
+                    skipped.insert(a.name.clone(), reason);
+                }
+            }
+            if skipped.contains_key(&a.name) {
+                // dependencies of a skipped task are not needed
+                continue;
+            }
             for b in a.resolve_depends(&CONFIG)? {
                 let b_idx = *indexes
                     .entry(b.name.clone())
@@ -44,7 +56,12 @@ impl Deps {
         }
         let (tx, _) = channel::unbounded();
         let sent = HashSet::new();
-        Ok(Self { graph, tx, sent })
+        Ok(Self {
+            graph,
+            tx,
+            sent,
+            skipped,
+        })
     }
 
     fn leaves(&self) -> Vec<Task> {
@@ -99,10 +116,44 @@ impl Deps {
         }
     }
 
+    /// removes every task that depends on `task` directly or transitively so they will not run
+    pub fn remove_dependents(&mut self, task: &Task) -> Vec<Task> {
+        let mut dependents = vec![];
+        if let Some(idx) = self
+            .graph
+            .node_indices()
+            .find(|&idx| &self.graph[idx] == task)
+        {
+            let mut stack = vec![idx];
+            let mut seen = HashSet::new();
+            while let Some(idx) = stack.pop() {
+                for dep in self.graph.neighbors_directed(idx, Direction::Incoming) {
+                    if seen.insert(dep) {
+                        dependents.push(self.graph[dep].clone());
+                        stack.push(dep);
+                    }
+                }
+            }
+        }
+        // remove all of them before emitting leaves so none are sent to run
+        self.graph
+            .retain_nodes(|g, idx| !dependents.contains(&g[idx]));
+        self.emit_leaves();
+        dependents
+    }
+
     pub fn all(&self) -> impl Iterator<Item = &Task> {
         self.graph.node_indices().map(|idx| &self.graph[idx])
     }
 
+    /// pairs of (task, dependency) in the graph
+    pub fn edges(&self) -> impl Iterator<Item = (&Task, &Task)> {
+        self.graph
+            .raw_edges()
+            .iter()
+            .map(|e| (&self.graph[e.source()], &self.graph[e.target()]))
+    }
+
     pub fn is_linear(&self) -> bool {
         !self.graph.node_indices().any(|idx| {
             self.graph
@@ -0,0 +1,206 @@
+use std::fmt::Write;
+use std::time::Duration;
+
+use crate::file::display_path;
+use crate::task::Task;
+
+/// collects the results of `mise run` into a JUnit XML report with a testcase per task
+#[derive(Debug, Default)]
+pub struct JunitReport {
+    cases: Vec<JunitTestCase>,
+}
+
+#[derive(Debug)]
+struct JunitTestCase {
+    name: String,
+    classname: String,
+    duration: Duration,
+    result: JunitResult,
+    stdout: Vec<String>,
+    stderr: Vec<String>,
+}
+
+#[derive(Debug)]
+enum JunitResult {
+    Running,
+    Passed,
+    Failed(i32),
+    Skipped(String),
+}
+
+impl JunitReport {
+    pub fn start(&mut self, task: &Task) {
+        self.case(task);
+    }
+
+    pub fn stdout(&mut self, task: &Task, line: &str) {
+        self.case(task).stdout.push(line.to_string());
+    }
+
+    pub fn stderr(&mut self, task: &Task, line: &str) {
+        self.case(task).stderr.push(line.to_string());
+    }
+
+    pub fn finish(&mut self, task: &Task, status: i32, duration: Duration) {
+        let case = self.case(task);
+        case.duration = duration;
+        case.result = match status {
+            0 => JunitResult::Passed,
+            status => JunitResult::Failed(status),
+        };
+    }
+
+    pub fn skip(&mut self, task: &Task, reason: &str) {
+        self.case(task).result = JunitResult::Skipped(reason.to_string());
+    }
+
+    /// marks a task as failed if it was not already, e.g.: from `Run::failed_tasks`
+    pub fn fail(&mut self, task: &Task, status: i32) {
+        let case = self.case(task);
+        if !matches!(case.result, JunitResult::Failed(_)) {
+            case.result = JunitResult::Failed(status);
+        }
+    }
+
+    fn case(&mut self, task: &Task) -> &mut JunitTestCase {
+        let idx = match self.cases.iter().position(|c| c.name == task.name) {
+            Some(idx) => idx,
+            None => {
+                self.cases.push(JunitTestCase {
+                    name: task.name.clone(),
+                    classname: display_path(&task.config_source),
+                    duration: Duration::default(),
+                    result: JunitResult::Running,
+                    stdout: vec![],
+                    stderr: vec![],
+                });
+                self.cases.len() - 1
+            }
+        };
+        &mut self.cases[idx]
+    }
+
+    pub fn to_xml(&self) -> String {
+        let count =
+            |f: fn(&JunitResult) -> bool| self.cases.iter().filter(|c| f(&c.result)).count();
+        let failures = count(|r| matches!(r, JunitResult::Failed(_)));
+        // tasks that never started or finished were not run because another task failed
+        let skipped = count(|r| matches!(r, JunitResult::Skipped(_) | JunitResult::Running));
+        let time = self
+            .cases
+            .iter()
+            .map(|c| c.duration)
+            .sum::<Duration>()
+            .as_secs_f64();
+        let attrs = format!(
+            r#"name="mise run" tests="{}" failures="{failures}" errors="0" skipped="{skipped}" time="{time:.3}""#,
+            self.cases.len()
+        );
+        let mut xml = String::new();
+        xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        let _ = writeln!(xml, "<testsuites {attrs}>");
+        let _ = writeln!(xml, "  <testsuite {attrs}>");
+        for case in &self.cases {
+            let _ = write!(
+                xml,
+                r#"    <testcase name="{}" classname="{}" time="{:.3}">"#,
+                escape(&case.name),
+                escape(&case.classname),
+                case.duration.as_secs_f64()
+            );
+            xml.push('\n');
+            match &case.result {
+                JunitResult::Passed => {}
+                JunitResult::Failed(status) => {
+                    let _ = writeln!(
+                        xml,
+                        r#"      <failure message="task failed with exit status {status}"/>"#
+                    );
+                }
+                JunitResult::Skipped(reason) => {
+                    let _ = writeln!(xml, r#"      <skipped message="{}"/>"#, escape(reason));
+                }
+                JunitResult::Running => {
+                    let _ = writeln!(xml, r#"      <skipped message="not run"/>"#);
+                }
+            }
+            if !case.stdout.is_empty() {
+                let out = escape(&case.stdout.join("\n"));
+                let _ = writeln!(xml, "      <system-out>{out}</system-out>");
+            }
+            if !case.stderr.is_empty() {
+                let err = escape(&case.stderr.join("\n"));
+                let _ = writeln!(xml, "      <system-err>{err}</system-err>");
+            }
+            xml.push_str("    </testcase>\n");
+        }
+        xml.push_str("  </testsuite>\n");
+        xml.push_str("</testsuites>\n");
+        xml
+    }
+}
+
+fn escape(s: &str) -> String {
+    let s = console::strip_ansi_codes(s);
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            // other control characters are not valid in XML 1.0
+            c if c.is_control() && !matches!(c, '\n' | '\r' | '\t') => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_junit_report() {
+        let task = |name: &str| Task {
+            name: name.to_string(),
+            config_source: "mise.toml".into(),
+            ..Default::default()
+        };
+        let mut report = JunitReport::default();
+        report.start(&task("lint"));
+        report.stdout(&task("lint"), "ok <3");
+        report.finish(&task("lint"), 0, Duration::from_millis(1500));
+        report.start(&task("test"));
+        report.stderr(&task("test"), "\x1b[31mfailed\x1b[0m");
+        report.finish(&task("test"), 2, Duration::from_millis(500));
+        report.skip(&task("build"), "sources up-to-date");
+        report.start(&task("deploy"));
+        assert_eq!(
+            report.to_xml(),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites name="mise run" tests="4" failures="1" errors="0" skipped="2" time="2.000">
+  <testsuite name="mise run" tests="4" failures="1" errors="0" skipped="2" time="2.000">
+    <testcase name="lint" classname="mise.toml" time="1.500">
+      <system-out>ok &lt;3</system-out>
+    </testcase>
+    <testcase name="test" classname="mise.toml" time="0.500">
+      <failure message="task failed with exit status 2"/>
+      <system-err>failed</system-err>
+    </testcase>
+    <testcase name="build" classname="mise.toml" time="0.000">
+      <skipped message="sources up-to-date"/>
+    </testcase>
+    <testcase name="deploy" classname="mise.toml" time="0.000">
+      <skipped message="not run"/>
+    </testcase>
+  </testsuite>
+</testsuites>
+"#
+        );
+    }
+}
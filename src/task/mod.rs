@@ -1,14 +1,15 @@
+use crate::cli::version::{ARCH, OS};
 use crate::config::config_file::toml::{deserialize_arr, TomlParser};
 use crate::config::Config;
-use crate::file;
 use crate::task::task_script_parser::{
     has_any_args_defined, replace_template_placeholders_with_args, TaskScriptParser,
 };
 use crate::tera::{get_tera, BASE_CONTEXT};
 use crate::ui::tree::TreeItem;
+use crate::{env, file};
 use console::{truncate_str, Color};
 use either::Either;
-use eyre::{eyre, Result};
+use eyre::{eyre, Result, WrapErr};
 use globset::Glob;
 use itertools::Itertools;
 use once_cell::sync::Lazy;
@@ -19,16 +20,26 @@ use std::cmp::Ordering;
 use std::collections::BTreeMap;
 use std::fmt::{Debug, Display, Formatter};
 use std::hash::{Hash, Hasher};
+use std::iter::once;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 use std::{ffi, fmt, path};
 use xx::regex;
 
 mod deps;
+mod junit;
+mod task_cache;
+mod task_import;
+mod task_include;
 mod task_script_parser;
 
 use crate::file::display_path;
 use crate::ui::style;
 pub use deps::Deps;
+pub use junit::JunitReport;
+pub use task_cache::TaskCache;
+pub use task_import::import_tasks;
+pub use task_include::TaskInclude;
 
 #[derive(Debug, Clone, Eq, PartialEq, Deserialize)]
 pub struct Task {
@@ -47,6 +58,8 @@ pub struct Task {
     #[serde(default)]
     pub dir: Option<PathBuf>,
     #[serde(default)]
+    pub tools: BTreeMap<String, String>,
+    #[serde(default)]
     pub hide: bool,
     #[serde(default)]
     pub raw: bool,
@@ -56,6 +69,20 @@ pub struct Task {
     pub outputs: Vec<String>,
     #[serde(default)]
     pub shell: Option<String>,
+    #[serde(default)]
+    pub timeout: Option<String>,
+    #[serde(default)]
+    pub retry: u32,
+    #[serde(default)]
+    pub retry_backoff: Option<String>,
+    #[serde(default)]
+    pub allow_failure: bool,
+    #[serde(default, rename = "if")]
+    pub condition: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_arr")]
+    pub os: Vec<String>,
+    #[serde(default, deserialize_with = "deserialize_arr")]
+    pub arch: Vec<String>,
 
     // normal type
     #[serde(default, deserialize_with = "deserialize_arr")]
@@ -136,13 +163,111 @@ impl Task {
             depends: p.parse_array("depends")?.unwrap_or_default(),
             dir: p.parse_str("dir")?,
             env: p.parse_env("env")?.unwrap_or_default(),
+            tools: p.parse_table_str("tools")?.unwrap_or_default(),
             file: Some(path.to_path_buf()),
             shell: p.parse_str("shell")?,
+            timeout: p.parse_str("timeout")?,
+            retry: p
+                .parse_int("retry")
+                .map(|r| {
+                    u32::try_from(r).wrap_err_with(|| {
+                        format!("invalid retry: {r}, expected a non-negative integer")
+                    })
+                })
+                .transpose()?
+                .unwrap_or_default(),
+            retry_backoff: p.parse_str("retry_backoff")?,
+            allow_failure: p.parse_bool("allow_failure").unwrap_or_default(),
+            condition: p.parse_str("if")?,
+            os: p
+                .parse_array("os")?
+                .or(p.parse_str("os")?.map(|s| vec![s]))
+                .unwrap_or_default(),
+            arch: p
+                .parse_array("arch")?
+                .or(p.parse_str("arch")?.map(|s| vec![s]))
+                .unwrap_or_default(),
             ..Default::default()
         };
         Ok(task)
     }
 
+    /// how long the task may run before it is killed, e.g.: "30s" or "5m"
+    pub fn timeout(&self) -> Result<Option<Duration>> {
+        self.timeout
+            .as_ref()
+            .map(|t| humantime::parse_duration(t).wrap_err_with(|| format!("invalid timeout: {t}")))
+            .transpose()
+    }
+
+    /// how long to wait before retrying the task, doubled after each attempt up to 5 minutes
+    /// (or `retry_backoff` itself if that is longer)
+    pub fn retry_backoff(&self, attempt: u32) -> Result<Duration> {
+        const MAX_BACKOFF: Duration = Duration::from_secs(5 * 60);
+        let backoff = match &self.retry_backoff {
+            Some(b) => humantime::parse_duration(b)
+                .wrap_err_with(|| format!("invalid retry_backoff: {b}"))?,
+            None => Duration::from_secs(1),
+        };
+        let doubled = backoff
+            .checked_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .unwrap_or(Duration::MAX);
+        Ok(doubled.min(MAX_BACKOFF.max(backoff)))
+    }
+
+    /// `if`, `os` and `arch` as shown in `mise tasks info`
+    pub fn condition_str(&self) -> Option<String> {
+        let mut conditions = vec![];
+        if !self.os.is_empty() {
+            conditions.push(format!("os={}", self.os.join(",")));
+        }
+        if !self.arch.is_empty() {
+            conditions.push(format!("arch={}", self.arch.join(",")));
+        }
+        if let Some(condition) = &self.condition {
+            conditions.push(format!("if={condition}"));
+        }
+        (!conditions.is_empty()).then(|| conditions.join(" "))
+    }
+
+    /// why the task should not run on this machine, if its `os`, `arch` or `if` conditions fail
+    pub fn skip_reason(&self) -> Result<Option<String>> {
+        if !self.os.is_empty()
+            && !self
+                .os
+                .iter()
+                .any(|os| os == &*OS || os == env::consts::FAMILY)
+        {
+            return Ok(Some(format!("os is not {}", self.os.join(" or "))));
+        }
+        if !self.arch.is_empty()
+            && !self
+                .arch
+                .iter()
+                .any(|arch| arch == &*ARCH || arch == env::consts::ARCH)
+        {
+            return Ok(Some(format!("arch is not {}", self.arch.join(" or "))));
+        }
+        if let Some(condition) = &self.condition {
+            let config_root = config_root(&self.config_source);
+            let mut tera_ctx = BASE_CONTEXT.clone();
+            if let Some(config_root) = config_root {
+                tera_ctx.insert("config_root", config_root);
+            }
+            let mut tera = get_tera(config_root);
+            let result = tera
+                .render_str(
+                    &format!("{{% if {condition} %}}true{{% endif %}}"),
+                    &tera_ctx,
+                )
+                .wrap_err_with(|| format!("failed to evaluate if condition: {condition}"))?;
+            if result != "true" {
+                return Ok(Some(format!("`{condition}` is false")));
+            }
+        }
+        Ok(None)
+    }
+
     // pub fn args(&self) -> impl Iterator<Item = String> {
     //     if let Some(script) = &self.script {
     //         // TODO: cli_args
@@ -161,15 +286,40 @@ impl Task {
         self
     }
 
+    /// namespaces a task of a monorepo package as `//path/to/package:task`, it runs in the
+    /// package directory and depends on tasks of the same package unless they start with `//`
+    pub fn with_package(
+        mut self,
+        package: &str,
+        dir: &Path,
+        tools: &BTreeMap<String, String>,
+        env: &BTreeMap<String, EitherStringOrBool>,
+    ) -> Self {
+        let namespace = |name: &String| match name {
+            name if is_monorepo_pattern(name) => name.clone(),
+            name => format!("//{package}:{name}"),
+        };
+        self.aliases = self.aliases.iter().map(namespace).collect();
+        self.depends = self.depends.iter().map(namespace).collect();
+        self.name = format!("//{package}:{}", self.name);
+        self.dir.get_or_insert_with(|| dir.to_path_buf());
+        for (tool, version) in tools {
+            self.tools.entry(tool.clone()).or_insert(version.clone());
+        }
+        for (k, v) in env {
+            self.env.entry(k.clone()).or_insert(v.clone());
+        }
+        self
+    }
+
     pub fn prefix(&self) -> String {
         format!("[{}]", self.name)
     }
 
     pub fn resolve_depends<'a>(&self, config: &'a Config) -> Result<Vec<&'a Task>> {
-        let tasks = config.tasks_with_aliases()?;
         self.depends
             .iter()
-            .map(|pat| match_tasks(tasks.clone(), pat))
+            .map(|pat| match_tasks(config, pat))
             .flatten_ok()
             .filter_ok(|t| t.name != self.name)
             .collect()
@@ -273,8 +423,8 @@ fn name_from_path(root: impl AsRef<Path>, path: impl AsRef<Path>) -> Result<Stri
         .join(":"))
 }
 
-fn match_tasks<'a>(tasks: BTreeMap<String, &'a Task>, pat: &str) -> Result<Vec<&'a Task>> {
-    let matches = tasks.get_matching(pat)?.into_iter().cloned().collect_vec();
+fn match_tasks<'a>(config: &'a Config, pat: &str) -> Result<Vec<&'a Task>> {
+    let matches = config.tasks_matching(pat)?;
     if matches.is_empty() {
         return Err(eyre!("task not found: {pat}"));
     };
@@ -282,6 +432,45 @@ fn match_tasks<'a>(tasks: BTreeMap<String, &'a Task>, pat: &str) -> Result<Vec<&
     Ok(matches)
 }
 
+/// `//path/to/package:task` or `...:task`
+pub fn is_monorepo_pattern(pat: &str) -> bool {
+    pat.starts_with("//") || pat.starts_with("...")
+}
+
+/// matches monorepo tasks against `//path/to/package:task`, `//path/...:task` for the packages
+/// under a directory, or `...:task` for every package. The task part can be a glob.
+pub fn match_monorepo_tasks<'a>(
+    tasks: &'a BTreeMap<String, Task>,
+    pat: &str,
+) -> Result<Vec<&'a Task>> {
+    let (package, task) = pat
+        .trim_start_matches("//")
+        .split_once(':')
+        .ok_or_else(|| eyre!("invalid task: {pat}, expected //path/to/package:task"))?;
+    let package_matches = |p: &str| match package.strip_suffix("...") {
+        Some(prefix) => {
+            let prefix = prefix.trim_end_matches('/');
+            prefix.is_empty() || p == prefix || p.starts_with(&format!("{prefix}/"))
+        }
+        None => p == package,
+    };
+    let matcher =
+        Glob::new(&task.split(':').collect::<PathBuf>().to_string_lossy())?.compile_matcher();
+    Ok(tasks
+        .values()
+        .filter(|t| {
+            once(&t.name).chain(t.aliases.iter()).any(|name| {
+                match name.trim_start_matches("//").split_once(':') {
+                    Some((p, name)) => {
+                        package_matches(p) && matcher.is_match(name.split(':').collect::<PathBuf>())
+                    }
+                    None => false,
+                }
+            })
+        })
+        .collect())
+}
+
 impl Default for Task {
     fn default() -> Self {
         Task {
@@ -292,11 +481,19 @@ impl Default for Task {
             depends: vec![],
             env: BTreeMap::new(),
             dir: None,
+            tools: BTreeMap::new(),
             hide: false,
             raw: false,
             sources: vec![],
             outputs: vec![],
             shell: None,
+            timeout: None,
+            retry: 0,
+            retry_backoff: None,
+            allow_failure: false,
+            condition: None,
+            os: vec![],
+            arch: vec![],
             run: vec![],
             args: vec![],
             file: None,
@@ -408,14 +605,16 @@ where
 
 #[cfg(test)]
 mod tests {
+    use std::collections::BTreeMap;
     use std::path::Path;
+    use std::time::Duration;
 
     use pretty_assertions::assert_eq;
 
     use crate::task::Task;
     use crate::test::reset;
 
-    use super::{config_root, name_from_path};
+    use super::{config_root, match_monorepo_tasks, name_from_path};
 
     #[test]
     fn test_from_path() {
@@ -429,6 +628,44 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_skip_reason() {
+        reset();
+        let task = |os: &[&str], condition: Option<&str>| Task {
+            name: "t".into(),
+            config_source: "mise.toml".into(),
+            os: os.iter().map(|s| s.to_string()).collect(),
+            condition: condition.map(|s| s.to_string()),
+            ..Default::default()
+        };
+        assert_eq!(task(&[], None).skip_reason().unwrap(), None);
+        assert_eq!(
+            task(&[std::env::consts::OS], None).skip_reason().unwrap(),
+            None
+        );
+        assert_eq!(
+            task(&["plan9"], None).skip_reason().unwrap(),
+            Some("os is not plan9".into())
+        );
+        assert_eq!(task(&[], Some("1 < 2")).skip_reason().unwrap(), None);
+        assert_eq!(
+            task(&[], Some("env.MISE_TEST_UNDEFINED is defined"))
+                .skip_reason()
+                .unwrap(),
+            Some("`env.MISE_TEST_UNDEFINED is defined` is false".into())
+        );
+    }
+
+    #[test]
+    fn test_retry_backoff() {
+        let mut task = Task::default();
+        assert_eq!(task.retry_backoff(1).unwrap(), Duration::from_secs(1));
+        assert_eq!(task.retry_backoff(3).unwrap(), Duration::from_secs(4));
+        assert_eq!(task.retry_backoff(100).unwrap(), Duration::from_secs(300));
+        task.retry_backoff = Some("10m".into());
+        assert_eq!(task.retry_backoff(5).unwrap(), Duration::from_secs(600));
+    }
+
     #[test]
     fn test_name_from_path() {
         reset();
@@ -469,4 +706,42 @@ mod tests {
             assert_eq!(config_root(&src), expected)
         }
     }
+
+    #[test]
+    fn test_match_monorepo_tasks() {
+        let tasks: BTreeMap<String, Task> = [
+            "//:lint",
+            "//services/api:lint",
+            "//services/api:test",
+            "//libs/core:lint",
+        ]
+        .into_iter()
+        .map(|name| {
+            let task = Task {
+                name: name.to_string(),
+                ..Default::default()
+            };
+            (name.to_string(), task)
+        })
+        .collect();
+        let names = |pat: &str| {
+            match_monorepo_tasks(&tasks, pat)
+                .unwrap()
+                .into_iter()
+                .map(|t| t.name.as_str())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names("//services/api:test"), vec!["//services/api:test"]);
+        assert_eq!(
+            names("//services/api:*"),
+            vec!["//services/api:lint", "//services/api:test"]
+        );
+        assert_eq!(
+            names("...:lint"),
+            vec!["//:lint", "//libs/core:lint", "//services/api:lint"]
+        );
+        assert_eq!(names("//services/...:lint"), vec!["//services/api:lint"]);
+        assert_eq!(names("//:lint"), vec!["//:lint"]);
+        assert!(match_monorepo_tasks(&tasks, "//services/api").is_err());
+    }
 }
@@ -0,0 +1,102 @@
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use eyre::{Result, WrapErr};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::cache::{CacheManager, CacheManagerBuilder};
+use crate::config::SETTINGS;
+use crate::file::display_path;
+use crate::rand::random_string;
+use crate::{dirs, file};
+
+/// outputs of a task stored in the task cache under a key derived from the task's inputs
+pub struct TaskCache {
+    archive: PathBuf,
+    entry: CacheManager<TaskCacheEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct TaskCacheEntry {
+    task: String,
+    outputs: Vec<PathBuf>,
+}
+
+impl TaskCache {
+    pub fn new(key: &str) -> Self {
+        let dir = Self::dir().join(key);
+        Self {
+            archive: dir.join("outputs.tar.gz"),
+            // the base keys (mise version, os, arch) would make a shared cache miss across machines
+            entry: CacheManagerBuilder::new(dir.join("task.msgpack.z"))
+                .without_base_cache_keys()
+                .build(),
+        }
+    }
+
+    /// `task_cache_dir` or `$MISE_CACHE_DIR/tasks`
+    pub fn dir() -> PathBuf {
+        SETTINGS
+            .task_cache_dir
+            .clone()
+            .unwrap_or_else(|| dirs::CACHE.join("tasks"))
+    }
+
+    /// extracts the cached outputs into `root`, returns false if nothing is cached for this key
+    pub fn restore(&self, root: &Path) -> Result<bool> {
+        if !self.archive.exists() {
+            return Ok(false);
+        }
+        let Some(entry) = self.entry.get() else {
+            return Ok(false);
+        };
+        debug!(
+            "restoring {} outputs of {} from {}",
+            entry.outputs.len(),
+            entry.task,
+            display_path(&self.archive)
+        );
+        let mut archive = tar::Archive::new(GzDecoder::new(File::open(&self.archive)?));
+        // restored outputs should be newer than the sources so mtime checks consider them fresh
+        archive.set_preserve_mtime(false);
+        archive.unpack(root).wrap_err_with(|| {
+            format!(
+                "failed to extract task cache: {}",
+                display_path(&self.archive)
+            )
+        })?;
+        Ok(true)
+    }
+
+    /// stores `outputs` (relative to `root`) in the cache
+    pub fn save(&self, task: &str, root: &Path, outputs: &[PathBuf]) -> Result<()> {
+        let outputs = outputs
+            .iter()
+            .map(|p| p.strip_prefix(root).unwrap_or(p).to_path_buf())
+            .collect::<Vec<_>>();
+        debug!(
+            "saving {} outputs of {task} to {}",
+            outputs.len(),
+            display_path(&self.archive)
+        );
+        file::create_dir_all(self.archive.parent().unwrap())?;
+        // write to a partial file first so a shared cache never exposes a half-written archive
+        let partial = self
+            .archive
+            .with_extension(format!("part-{}", random_string(8)));
+        let mut tar =
+            tar::Builder::new(GzEncoder::new(File::create(&partial)?, Compression::fast()));
+        for path in &outputs {
+            tar.append_path_with_name(root.join(path), path)?;
+        }
+        tar.into_inner()?.finish()?;
+        file::rename(&partial, &self.archive)?;
+        self.entry.write(&TaskCacheEntry {
+            task: task.to_string(),
+            outputs,
+        })
+    }
+}
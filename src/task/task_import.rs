@@ -0,0 +1,228 @@
+use std::path::Path;
+
+use eyre::{bail, Result, WrapErr};
+use indexmap::IndexMap;
+use serde_derive::Deserialize;
+use xx::regex;
+
+use crate::file;
+use crate::file::display_path;
+use crate::task::Task;
+
+/// imports the scripts of a `package.json`, targets of a `Makefile` or recipes of a `justfile`
+/// as tasks named `npm:<script>`, `make:<target>` and `just:<recipe>`
+pub fn import_tasks(path: &Path) -> Result<Vec<Task>> {
+    let file = path.file_name().unwrap_or_default().to_string_lossy();
+    let raw = file::read_to_string(path)?;
+    let quoted = shell_escape::escape(file.clone());
+    let (prefix, run, tasks) = match file.to_lowercase().as_str() {
+        "package.json" => (
+            "npm",
+            "npm run".to_string(),
+            parse_package_json(&raw)
+                .wrap_err_with(|| format!("failed to parse {}", display_path(path)))?,
+        ),
+        "makefile" | "gnumakefile" => ("make", format!("make -f {quoted}"), parse_makefile(&raw)),
+        "justfile" | ".justfile" => (
+            "just",
+            format!("just --justfile {quoted}"),
+            parse_justfile(&raw),
+        ),
+        _ => bail!(
+            "unsupported task import: {}, expected package.json, Makefile or justfile",
+            display_path(path)
+        ),
+    };
+    Ok(tasks
+        .into_iter()
+        .map(|(name, description, hide)| Task {
+            name: format!("{prefix}:{name}"),
+            description,
+            hide,
+            config_source: path.to_path_buf(),
+            dir: path.parent().map(|p| p.to_path_buf()),
+            run: vec![format!(
+                "{run} {}",
+                shell_escape::escape(name.as_str().into())
+            )],
+            ..Default::default()
+        })
+        .collect())
+}
+
+#[derive(Debug, Deserialize)]
+struct PackageJson {
+    #[serde(default)]
+    scripts: IndexMap<String, String>,
+}
+
+/// scripts have no description so the script itself is used
+fn parse_package_json(raw: &str) -> Result<Vec<(String, String, bool)>> {
+    let package: PackageJson = serde_json::from_str(raw)?;
+    Ok(package
+        .scripts
+        .into_iter()
+        .map(|(name, script)| (name, script, false))
+        .collect())
+}
+
+/// targets with a `## description` comment after them or a `#` comment on the line above
+fn parse_makefile(raw: &str) -> Vec<(String, String, bool)> {
+    let mut tasks: Vec<(String, String, bool)> = vec![];
+    let mut comment = None;
+    let re = regex!(
+        r"^([A-Za-z0-9_][A-Za-z0-9_./-]*(?:[ \t]+[A-Za-z0-9_][A-Za-z0-9_./-]*)*)[ \t]*::?(?:[^=]|$)(?:.*##[ \t]*(.*))?"
+    );
+    for line in raw.lines() {
+        if let Some(c) = line.strip_prefix('#') {
+            comment = Some(c.trim_start_matches('#').trim().to_string());
+            continue;
+        }
+        let caps = re.captures(line);
+        if let Some(caps) = caps {
+            let description = caps
+                .get(2)
+                .map(|d| d.as_str().trim().to_string())
+                .or(comment.take())
+                .unwrap_or_default();
+            for target in caps[1].split_whitespace() {
+                if !tasks.iter().any(|(name, _, _)| name == target) {
+                    tasks.push((target.to_string(), description.clone(), false));
+                }
+            }
+        }
+        comment = None;
+    }
+    tasks
+}
+
+/// recipes with a `#` doc comment on the line above, `_` prefixed or `[private]` recipes are hidden
+fn parse_justfile(raw: &str) -> Vec<(String, String, bool)> {
+    let mut tasks = vec![];
+    let mut comment = None;
+    let mut private = false;
+    let re = regex!(r"^@?([A-Za-z_][A-Za-z0-9_-]*)(?:[ \t]+[^:]*)?:(?:[^=]|$)");
+    for line in raw.lines() {
+        if let Some(c) = line.strip_prefix('#') {
+            if !c.starts_with('!') {
+                comment = Some(c.trim().to_string());
+            }
+            continue;
+        }
+        if let Some(attrs) = line.trim().strip_prefix('[') {
+            private |= attrs.split([',', ']']).any(|a| a.trim() == "private");
+            continue;
+        }
+        let caps = re.captures(line);
+        if let Some(caps) = caps {
+            let name = caps[1].to_string();
+            let hide = private || name.starts_with('_');
+            tasks.push((name, comment.take().unwrap_or_default(), hide));
+        }
+        comment = None;
+        private = false;
+    }
+    tasks
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_package_json() {
+        let tasks = parse_package_json(
+            r#"{"name": "app", "scripts": {"build": "tsc", "test": "vitest run"}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            tasks,
+            vec![
+                ("build".into(), "tsc".into(), false),
+                ("test".into(), "vitest run".into(), false),
+            ]
+        );
+        assert_eq!(parse_package_json(r#"{"name": "app"}"#).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn test_import_tasks_quotes_names() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("package.json");
+        file::write(
+            &path,
+            r#"{"scripts": {"build": "tsc", "lint; rm -rf .": "eslint"}}"#,
+        )
+        .unwrap();
+        let tasks = import_tasks(&path).unwrap();
+        assert_eq!(tasks[0].run, vec!["npm run build"]);
+        assert_eq!(tasks[1].run, vec!["npm run 'lint; rm -rf .'"]);
+    }
+
+    #[test]
+    fn test_parse_makefile() {
+        let tasks = parse_makefile(
+            r#"CC := gcc
+.PHONY: build test
+
+# Build the app
+build: main.o
+	$(CC) -o app main.o
+
+test: build ## Run the tests
+	./app --test
+
+%.o: %.c
+	$(CC) -c $<
+
+clean install:
+	rm -f app
+"#,
+        );
+        assert_eq!(
+            tasks,
+            vec![
+                ("build".into(), "Build the app".into(), false),
+                ("test".into(), "Run the tests".into(), false),
+                ("clean".into(), "".into(), false),
+                ("install".into(), "".into(), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_justfile() {
+        let tasks = parse_justfile(
+            r#"set shell := ["bash", "-c"]
+version := "1.0"
+
+# Build the app
+build target="debug": _setup
+    cargo build --profile {{target}}
+
+alias b := build
+
+[private]
+helper:
+    echo helper
+
+_setup:
+    echo setup
+
+@test *args:
+    cargo test {{args}}
+"#,
+        );
+        assert_eq!(
+            tasks,
+            vec![
+                ("build".into(), "Build the app".into(), false),
+                ("helper".into(), "".into(), true),
+                ("_setup".into(), "".into(), true),
+                ("test".into(), "".into(), false),
+            ]
+        );
+    }
+}
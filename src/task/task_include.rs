@@ -0,0 +1,224 @@
+use std::path::{Path, PathBuf};
+
+use eyre::{bail, Result};
+
+use crate::file::display_path;
+use crate::git::Git;
+use crate::hash::hash_to_str;
+use crate::http::HTTP;
+use crate::lock_file::LockFile;
+use crate::{dirs, file};
+
+/// a `task_config.includes` or `extends` entry fetched from a git repository or an archive
+/// over https
+///
+/// - `git::https://github.com/org/tasks.git//path/to/tasks?ref=v1.0.0`
+/// - `https://example.com/tasks.tar.gz//path/to/tasks`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TaskInclude {
+    pub source: String,
+    pub url: String,
+    pub gitref: Option<String>,
+    pub subdir: Option<PathBuf>,
+    kind: TaskIncludeKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum TaskIncludeKind {
+    Git,
+    Archive,
+}
+
+impl TaskInclude {
+    /// returns None for local paths
+    pub fn parse(source: &str) -> Option<Self> {
+        let (kind, rest) = if let Some(rest) = source.strip_prefix("git::") {
+            (TaskIncludeKind::Git, rest)
+        } else if source.starts_with("https://") || source.starts_with("http://") {
+            (TaskIncludeKind::Archive, source)
+        } else {
+            return None;
+        };
+        let (rest, gitref) = match (&kind, rest.split_once("?ref=")) {
+            (TaskIncludeKind::Git, Some((rest, gitref))) => (rest, Some(gitref.to_string())),
+            _ => (rest, None),
+        };
+        // `//` after the scheme separates the url from a subdirectory inside of it
+        let scheme_end = rest.find("://").map(|i| i + 3).unwrap_or_default();
+        let (url, subdir) = match rest[scheme_end..].find("//") {
+            Some(i) => (
+                &rest[..scheme_end + i],
+                Some(PathBuf::from(&rest[scheme_end + i + 2..])),
+            ),
+            None => (rest, None),
+        };
+        Some(Self {
+            source: source.to_string(),
+            url: url.to_string(),
+            gitref,
+            subdir,
+            kind,
+        })
+    }
+
+    /// `$MISE_CACHE_DIR/task-includes/<hash>`
+    pub fn cache_dir(&self) -> PathBuf {
+        dirs::CACHE
+            .join("task-includes")
+            .join(hash_to_str(&(&self.url, &self.gitref)))
+    }
+
+    /// fetches the include into the cache if it is not already there (or if `refresh` is set)
+    /// and returns the local directory to load tasks from or the config file to extend
+    pub fn fetch(&self, refresh: bool) -> Result<PathBuf> {
+        if self.url.starts_with("http://") {
+            bail!(
+                "{} is not fetched over https, task includes must use https",
+                self.source
+            );
+        }
+        let dir = self.cache_dir();
+        let _lock = LockFile::new(&dir)
+            .with_callback(|l| debug!("waiting for lock on {}", display_path(l)))
+            .lock()?;
+        match self.kind {
+            TaskIncludeKind::Git => self.fetch_git(&dir, refresh)?,
+            TaskIncludeKind::Archive => self.fetch_archive(&dir, refresh)?,
+        }
+        let dir = match &self.subdir {
+            Some(subdir) => dir.join(subdir),
+            None => dir,
+        };
+        if !dir.exists() {
+            bail!("{} not found in {}", display_path(&dir), self.source);
+        }
+        Ok(dir)
+    }
+
+    fn fetch_git(&self, dir: &Path, refresh: bool) -> Result<()> {
+        let git = Git::new(dir);
+        if !git.exists() {
+            info!("fetching {}", self.source);
+            // cloned next to the include dir and moved into place once the ref is checked out so
+            // a failed checkout doesn't leave the default branch in the cache
+            let tmp = dir.with_file_name(format!(
+                "{}.partial",
+                dir.file_name().unwrap_or_default().to_string_lossy()
+            ));
+            file::remove_all(&tmp)?;
+            let clone = || -> Result<()> {
+                let git = Git::new(&tmp);
+                git.clone(&self.url)?;
+                if let Some(gitref) = &self.gitref {
+                    git.update(Some(gitref.clone()))?;
+                }
+                Ok(())
+            };
+            if let Err(err) = clone() {
+                file::remove_all(&tmp)?;
+                return Err(err);
+            }
+            file::remove_all(dir)?;
+            file::rename(&tmp, dir)?;
+        } else if refresh {
+            info!("updating {}", self.source);
+            git.update(self.gitref.clone())?;
+        }
+        Ok(())
+    }
+
+    fn fetch_archive(&self, dir: &Path, refresh: bool) -> Result<()> {
+        if dir.exists() && !refresh {
+            return Ok(());
+        }
+        info!("fetching {}", self.source);
+        let filename = self.url.rsplit('/').next().unwrap_or_default();
+        // next to the include dir so it is covered by its lock, other includes may have the same
+        // filename and are fetched in parallel
+        let tmp = dir.with_file_name(format!(
+            "{}-{filename}",
+            dir.file_name().unwrap_or_default().to_string_lossy()
+        ));
+        HTTP.download_file(&self.url, &tmp, None)?;
+        file::remove_all(dir)?;
+        file::create_dir_all(dir)?;
+        if filename.ends_with(".zip") {
+            file::unzip(&tmp, dir)?;
+        } else {
+            file::untar(&tmp, dir)?;
+        }
+        file::remove_file(&tmp)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(TaskInclude::parse("tasks"), None);
+        assert_eq!(TaskInclude::parse("/tmp/tasks.toml"), None);
+        let include =
+            TaskInclude::parse("git::https://github.com/org/tasks.git//ci/tasks?ref=v1.0.0")
+                .unwrap();
+        assert_eq!(include.url, "https://github.com/org/tasks.git");
+        assert_eq!(include.gitref, Some("v1.0.0".into()));
+        assert_eq!(include.subdir, Some("ci/tasks".into()));
+        assert_eq!(include.kind, TaskIncludeKind::Git);
+        let include = TaskInclude::parse("git::git@github.com:org/tasks.git").unwrap();
+        assert_eq!(include.url, "git@github.com:org/tasks.git");
+        assert_eq!(include.gitref, None);
+        assert_eq!(include.subdir, None);
+        let include = TaskInclude::parse("https://example.com/tasks.tar.gz").unwrap();
+        assert_eq!(include.url, "https://example.com/tasks.tar.gz");
+        assert_eq!(include.subdir, None);
+        assert_eq!(include.kind, TaskIncludeKind::Archive);
+        let include = TaskInclude::parse("https://example.com/tasks.tar.gz//tasks").unwrap();
+        assert_eq!(include.url, "https://example.com/tasks.tar.gz");
+        assert_eq!(include.subdir, Some("tasks".into()));
+    }
+
+    #[test]
+    fn test_fetch_git_missing_ref() {
+        let repo = tempfile::tempdir().unwrap();
+        let git = |args: &[&str]| {
+            let user = ["-c", "user.name=mise", "-c", "user.email=mise@example.com"];
+            crate::cmd::cmd("git", user.iter().chain(args))
+                .dir(repo.path())
+                .stdout_null()
+                .stderr_null()
+                .run()
+                .unwrap()
+        };
+        git(&["init", "-q"]);
+        git(&["commit", "-q", "--allow-empty", "-m", "init"]);
+        let url = format!("file://{}", repo.path().display());
+        let include = TaskInclude::parse(&format!("git::{url}?ref=missing")).unwrap();
+        assert!(include.fetch(false).is_err());
+        assert!(!include.cache_dir().exists());
+        let include = TaskInclude::parse(&format!("git::{url}")).unwrap();
+        assert!(include.fetch(false).unwrap().join(".git").exists());
+        file::remove_all(include.cache_dir()).unwrap();
+    }
+
+    #[test]
+    fn test_fetch_http() {
+        for source in [
+            "http://example.com/tasks.tar.gz",
+            "git::http://example.com/tasks.git",
+        ] {
+            let err = TaskInclude::parse(source)
+                .unwrap()
+                .fetch(false)
+                .unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                format!("{source} is not fetched over https, task includes must use https")
+            );
+        }
+    }
+}
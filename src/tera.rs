@@ -11,8 +11,9 @@ use rand::thread_rng;
 use tera::{Context, Tera, Value};
 use versions::{Requirement, Versioning};
 
+use crate::cache::CacheManagerBuilder;
 use crate::cmd::cmd;
-use crate::{env, hash};
+use crate::{dirs, env, hash};
 
 pub static BASE_CONTEXT: Lazy<Context> = Lazy::new(|| {
     let mut context = Context::new();
@@ -32,22 +33,7 @@ pub static BASE_CONTEXT: Lazy<Context> = Lazy::new(|| {
 pub fn get_tera(dir: Option<&Path>) -> Tera {
     let mut tera = Tera::default();
     let dir = dir.map(PathBuf::from);
-    tera.register_function(
-        "exec",
-        move |args: &HashMap<String, Value>| -> tera::Result<Value> {
-            match args.get("command") {
-                Some(Value::String(command)) => {
-                    let mut cmd = cmd("bash", ["-c", command]).full_env(&*env::PRISTINE_ENV);
-                    if let Some(dir) = &dir {
-                        cmd = cmd.dir(dir);
-                    }
-                    let result = cmd.read()?;
-                    Ok(Value::String(result))
-                }
-                _ => Err("exec command must be a string".into()),
-            }
-        },
-    );
+    tera.register_function("exec", exec(dir, None));
     tera.register_function(
         "arch",
         move |_args: &HashMap<String, Value>| -> tera::Result<Value> {
@@ -311,9 +297,66 @@ pub fn get_tera(dir: Option<&Path>) -> Tera {
     tera
 }
 
+/// like `get_tera` but `exec()` results cached with `cache_duration` are also invalidated when
+/// `config_file` changes
+pub fn get_tera_for_config(config_file: &Path) -> Tera {
+    let dir = config_file.parent();
+    let mut tera = get_tera(dir);
+    tera.register_function(
+        "exec",
+        exec(dir.map(PathBuf::from), Some(config_file.to_path_buf())),
+    );
+    tera
+}
+
+/// `exec(command, [cache_key], [cache_duration])`, if `cache_duration` is set the output is cached
+/// in `$MISE_CACHE_DIR/exec` so it is not run on every prompt
+fn exec(dir: Option<PathBuf>, config_file: Option<PathBuf>) -> impl tera::Function {
+    move |args: &HashMap<String, Value>| -> tera::Result<Value> {
+        let command = match args.get("command") {
+            Some(Value::String(command)) => command,
+            _ => return Err("exec command must be a string".into()),
+        };
+        let run = || -> eyre::Result<String> {
+            let mut cmd = cmd("bash", ["-c", command]).full_env(&*env::PRISTINE_ENV);
+            if let Some(dir) = &dir {
+                cmd = cmd.dir(dir);
+            }
+            Ok(cmd.read()?)
+        };
+        let cache_duration = match args.get("cache_duration") {
+            Some(Value::String(d)) => Some(humantime::parse_duration(d).map_err(|err| {
+                tera::Error::msg(format!("invalid exec cache_duration: {d}: {err}"))
+            })?),
+            Some(_) => return Err("exec cache_duration must be a string".into()),
+            None => None,
+        };
+        let result = match cache_duration {
+            Some(cache_duration) => {
+                let cache_key = args.get("cache_key").map(|k| k.to_string());
+                let key = hash::hash_to_str(&(command, &dir, cache_key));
+                let mut cache = CacheManagerBuilder::new(
+                    dirs::CACHE.join("exec").join(format!("{key}.msgpack.z")),
+                )
+                .with_fresh_duration(Some(cache_duration))
+                .with_private();
+                if let Some(config_file) = &config_file {
+                    cache = cache.with_fresh_file(config_file.clone());
+                }
+                cache.build().get_or_try_init(run).cloned()
+            }
+            None => run(),
+        };
+        result
+            .map(Value::String)
+            .map_err(|err| tera::Error::msg(format!("{err:#}")))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::file;
     use crate::test::reset;
     use insta::assert_snapshot;
     use pretty_assertions::assert_str_eq;
@@ -578,6 +621,23 @@ mod tests {
         assert_eq!(s.trim(), "ok");
     }
 
+    #[test]
+    fn test_exec() {
+        reset();
+        file::remove_all(dirs::CACHE.join("exec")).unwrap();
+        assert_eq!(render("{{ exec(command='echo hello') }}"), "hello");
+        let cached = "{{ exec(command='echo $RANDOM$RANDOM', cache_duration='1h') }}";
+        assert_eq!(render(cached), render(cached));
+        let cache_files = file::ls(&dirs::CACHE.join("exec")).unwrap();
+        assert_eq!(cache_files.len(), 1);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = cache_files[0].metadata().unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+
     fn render(s: &str) -> String {
         let config_root = Path::new("/");
         let mut tera_ctx = BASE_CONTEXT.clone();
@@ -1,4 +1,5 @@
 use std::sync::mpsc;
+use std::sync::mpsc::RecvTimeoutError;
 use std::thread;
 use std::time::Duration;
 
@@ -8,6 +9,17 @@ pub fn run_with_timeout<F, T>(f: F, timeout: Duration) -> Result<T>
 where
     F: FnOnce() -> Result<T> + Send,
     T: Send,
+{
+    run_with_timeout_or_else(f, timeout, || {})
+}
+
+/// Like `run_with_timeout` but calls `on_timeout` once the timeout is reached. This still waits
+/// for `f` to return so `on_timeout` should make it return, e.g.: by killing the process `f` waits on.
+pub fn run_with_timeout_or_else<F, T, C>(f: F, timeout: Duration, on_timeout: C) -> Result<T>
+where
+    F: FnOnce() -> Result<T> + Send,
+    T: Send,
+    C: FnOnce(),
 {
     let (tx, rx) = mpsc::channel();
     thread::scope(|s| {
@@ -16,6 +28,10 @@ where
             // If sending fails, the timeout has already been reached.
             let _ = tx.send(result);
         });
-        rx.recv_timeout(timeout).context("timed out")
+        let result = rx.recv_timeout(timeout);
+        if let Err(RecvTimeoutError::Timeout) = result {
+            on_timeout();
+        }
+        result.context("timed out")
     })?
 }
@@ -4,7 +4,7 @@ use eyre::Result;
 use itertools::Itertools;
 
 use crate::cli::args::{BackendArg, ToolArg};
-use crate::config::Config;
+use crate::config::{Config, SETTINGS};
 use crate::env;
 use crate::errors::Error;
 use crate::toolset::{ToolRequest, ToolSource, Toolset};
@@ -38,7 +38,7 @@ impl ToolsetBuilder {
         self.load_runtime_env(&mut toolset, env::vars().collect())?;
         self.load_runtime_args(&mut toolset)?;
         if let Err(err) = toolset.resolve() {
-            if Error::is_argument_err(&err) {
+            if Error::is_argument_err(&err) || SETTINGS.locked {
                 return Err(err);
             }
             warn!("failed to resolve toolset: {err:#}");
@@ -391,6 +391,13 @@ impl Toolset {
                     trace!("skipping up-to-date version {tv}");
                     return None;
                 }
+                if out.current.is_some() {
+                    out.current_created_at = t
+                        .list_remote_versions_with_info()
+                        .ok()
+                        .and_then(|versions| versions.into_iter().find(|v| v.version == tv.version))
+                        .and_then(|v| v.created_at);
+                }
                 if bump {
                     let prefix = prefix.unwrap_or_default();
                     let old = tv.request.version();
@@ -722,6 +729,11 @@ pub struct OutdatedInfo {
     pub requested: String,
     #[tabled(display_with("Self::display_current", self))]
     pub current: Option<String>,
+    /// when the current version was released if the backend knows, shown as its age
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[tabled(rename = "age")]
+    #[tabled(display_with("Self::display_age", self))]
+    pub current_created_at: Option<String>,
     #[tabled(skip)]
     pub bump: Option<String>,
     pub latest: String,
@@ -733,6 +745,7 @@ impl OutdatedInfo {
         Self {
             name: tv.backend.short.to_string(),
             current: None,
+            current_created_at: None,
             requested: tv.request.version(),
             tool_request: tv.request.clone(),
             tool_version: tv,
@@ -749,6 +762,26 @@ impl OutdatedInfo {
             "[MISSING]".to_string()
         }
     }
+
+    fn display_age(&self) -> String {
+        self.current_created_at
+            .as_deref()
+            .and_then(age)
+            .unwrap_or_default()
+    }
+}
+
+/// how long ago a version was released, e.g.: "3 months" for "2024-01-06T21:26:52Z"
+fn age(created_at: &str) -> Option<String> {
+    let created_at = chrono::DateTime::parse_from_rfc3339(created_at).ok()?;
+    let days = (chrono::Utc::now() - created_at.with_timezone(&chrono::Utc)).num_days();
+    let (n, unit) = match days {
+        d if d < 1 => return Some("today".to_string()),
+        d if d < 31 => (d, "day"),
+        d if d < 365 => (d / 30, "month"),
+        d => (d / 365, "year"),
+    };
+    Some(format!("{n} {unit}{}", if n == 1 { "" } else { "s" }))
 }
 
 impl Display for OutdatedInfo {
@@ -775,7 +808,7 @@ mod tests {
     use pretty_assertions::assert_eq;
     use test_log::test;
 
-    use super::{check_semver_bump, is_outdated_version, ToolVersionOptions};
+    use super::{age, check_semver_bump, is_outdated_version, ToolVersionOptions};
 
     #[test]
     fn test_is_outdated_version() {
@@ -850,4 +883,14 @@ mod tests {
             .collect(),
         );
     }
+
+    #[test]
+    fn test_age() {
+        let ago = |days| (chrono::Utc::now() - chrono::Duration::days(days)).to_rfc3339();
+        assert_eq!(age(&ago(0)), Some("today".to_string()));
+        assert_eq!(age(&ago(1)), Some("1 day".to_string()));
+        assert_eq!(age(&ago(65)), Some("2 months".to_string()));
+        assert_eq!(age(&ago(800)), Some("2 years".to_string()));
+        assert_eq!(age("not a date"), None);
+    }
 }
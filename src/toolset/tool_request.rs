@@ -7,6 +7,8 @@ use xx::file;
 
 use crate::backend::Backend;
 use crate::cli::args::BackendArg;
+use crate::config::SETTINGS;
+use crate::file::display_path;
 use crate::runtime_symlinks::is_runtime_symlink;
 use crate::toolset::{ToolSource, ToolVersion, ToolVersionOptions};
 use crate::{backend, lockfile};
@@ -205,7 +207,21 @@ impl ToolRequest {
 
     pub fn lockfile_resolve(&self) -> Result<Option<String>> {
         if let Some(path) = self.source().path() {
-            return lockfile::get_locked_version(path, &self.backend().short);
+            let version = lockfile::get_locked_version(path, self.backend(), &self.version())?;
+            if version.is_none()
+                && SETTINGS.locked
+                && matches!(self.source(), ToolSource::MiseToml(_))
+                && matches!(
+                    self,
+                    Self::Version { .. } | Self::Prefix { .. } | Self::Sub { .. }
+                )
+            {
+                bail!(
+                    "{self} is not in the lockfile for {} and `locked` is enabled",
+                    display_path(path)
+                );
+            }
+            return Ok(version);
         }
         Ok(None)
     }
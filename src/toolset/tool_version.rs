@@ -7,7 +7,7 @@ use std::path::PathBuf;
 use crate::backend;
 use crate::backend::{ABackend, Backend};
 use crate::cli::args::BackendArg;
-use crate::config::Config;
+use crate::config::{Config, SETTINGS};
 #[cfg(windows)]
 use crate::file;
 use crate::hash::hash_to_str;
@@ -39,7 +39,7 @@ impl ToolVersion {
         request: ToolRequest,
         latest_versions: bool,
     ) -> Result<Self> {
-        if !latest_versions {
+        if !latest_versions || SETTINGS.locked {
             if let Some(v) = request.lockfile_resolve()? {
                 let tv = Self::new(backend, request.clone(), v);
                 return Ok(tv);